@@ -0,0 +1,22 @@
+//! Decodes arbitrary bytes as a geometry, which must fail gracefully on invalid input.
+//!
+//! Run with `cargo fuzz run read_geometry_container -- -rss_limit_mb=256 -malloc_limit_mb=64`
+//! to also catch excessive allocations.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use postgis_diesel::types::{
+    Force2D, Force3DM, Force3DZ, Force4D, GeometryContainer, PointAny, ReadEwkb, SridPolicy,
+};
+
+type Geometry = GeometryContainer<0, PointAny<0>>;
+
+fuzz_target!(|data: &[u8]| {
+    // any SRID and any dimension, to decode as much of the input as possible
+    let _ = Geometry::read_ewkb(data, SridPolicy::Any);
+    // and coerced to each dimension
+    let _ = Force2D::<Geometry>::read_ewkb(data, SridPolicy::Any);
+    let _ = Force3DZ::<Geometry>::read_ewkb(data, SridPolicy::Any);
+    let _ = Force3DM::<Geometry>::read_ewkb(data, SridPolicy::Any);
+    let _ = Force4D::<Geometry>::read_ewkb(data, SridPolicy::Any);
+});
@@ -0,0 +1,370 @@
+use std::{convert::TryFrom, fmt::Debug};
+
+use byteorder::LittleEndian;
+
+use diesel::{
+    deserialize::{self, FromSql},
+    pg::{self, Pg},
+    serialize::{self, IsNull, Output, ToSql},
+};
+
+use crate::{
+    error::{ErrorLocation, PostgisError},
+    ewkb::{invalid_geometry, read_srid, ReadEwkb, ReadOptions, WriteEwkb},
+    sql_types::Geometry,
+    types::*,
+};
+
+impl<G> AnySrid<G> {
+    pub fn new(srid: Option<u32>, geometry: G) -> Self {
+        Self { srid, geometry }
+    }
+}
+
+impl<G> FromSql<Geometry, Pg> for AnySrid<G>
+where
+    G: ReadEwkb,
+{
+    fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
+        Ok(Self::read_ewkb(bytes.as_bytes(), SridPolicy::Any)?)
+    }
+}
+
+/// Reads `G` with [`SridPolicy::Any`], in place of the policy of the options.
+impl<G> ReadEwkb for AnySrid<G>
+where
+    G: ReadEwkb,
+{
+    fn read_ewkb_with(bytes: &[u8], options: ReadOptions) -> Result<Self, PostgisError> {
+        let geometry = G::read_ewkb_with(bytes, options.with_policy(SridPolicy::Any))?;
+        Ok(Self::new(read_srid(bytes)?, geometry))
+    }
+}
+
+impl<G> ToSql<Geometry, Pg> for AnySrid<G>
+where
+    G: WriteEwkb + Debug,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        self.geometry
+            .write_ewkb::<LittleEndian, _>(self.srid, out)?;
+        Ok(IsNull::No)
+    }
+}
+
+/// Returns the geometry of `value` if its runtime SRID is `SRID`. As no EWKB is decoded, the
+/// errors of the conversion have offset 0.
+fn check_srid<const SRID: u32, G>(value: AnySrid<G>) -> Result<G, PostgisError> {
+    if value.srid != Some(SRID) {
+        return Err(PostgisError::SridMismatch {
+            expected: SRID,
+            found: value.srid,
+            location: ErrorLocation::default(),
+        });
+    }
+    Ok(value.geometry)
+}
+
+fn point<const SRID: u32, Q, P>(point: &Q) -> Result<P, PostgisError>
+where
+    Q: PointT<0>,
+    P: PointT<SRID>,
+{
+    P::new_point(point.get_x(), point.get_y(), point.get_z(), point.get_m())
+        .map_err(invalid_geometry(0))
+}
+
+fn points<const SRID: u32, Q, P>(points: &[Q]) -> Result<Vec<P>, PostgisError>
+where
+    Q: PointT<0>,
+    P: PointT<SRID>,
+{
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| point::<SRID, Q, P>(p).map_err(|e| e.nested(i as u32)))
+        .collect()
+}
+
+fn line_string<const SRID: u32, Q, P>(
+    line: &LineString<0, Q>,
+) -> Result<LineString<SRID, P>, PostgisError>
+where
+    Q: PointT<0>,
+    P: PointT<SRID>,
+{
+    Ok(LineString {
+        points: points::<SRID, Q, P>(&line.points)?,
+    })
+}
+
+fn polygon<const SRID: u32, Q, P>(polygon: &Polygon<0, Q>) -> Result<Polygon<SRID, P>, PostgisError>
+where
+    Q: PointT<0>,
+    P: PointT<SRID>,
+{
+    Ok(Polygon {
+        rings: polygon
+            .rings
+            .iter()
+            .enumerate()
+            .map(|(i, g)| line_string::<SRID, Q, P>(g).map_err(|e| e.nested(i as u32)))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn multi_point<const SRID: u32, Q, P>(
+    multi_point: &MultiPoint<0, Q>,
+) -> Result<MultiPoint<SRID, P>, PostgisError>
+where
+    Q: PointT<0>,
+    P: PointT<SRID>,
+{
+    Ok(MultiPoint {
+        points: points::<SRID, Q, P>(&multi_point.points)?,
+    })
+}
+
+fn multi_line_string<const SRID: u32, Q, P>(
+    multi_line: &MultiLineString<0, Q>,
+) -> Result<MultiLineString<SRID, P>, PostgisError>
+where
+    Q: PointT<0>,
+    P: PointT<SRID>,
+{
+    Ok(MultiLineString {
+        lines: multi_line
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(i, g)| line_string::<SRID, Q, P>(g).map_err(|e| e.nested(i as u32)))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn multi_polygon<const SRID: u32, Q, P>(
+    multi_polygon: &MultiPolygon<0, Q>,
+) -> Result<MultiPolygon<SRID, P>, PostgisError>
+where
+    Q: PointT<0>,
+    P: PointT<SRID>,
+{
+    Ok(MultiPolygon {
+        polygons: multi_polygon
+            .polygons
+            .iter()
+            .enumerate()
+            .map(|(i, g)| polygon::<SRID, Q, P>(g).map_err(|e| e.nested(i as u32)))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn geometry_collection<const SRID: u32, Q, P>(
+    collection: &GeometryCollection<0, Q>,
+) -> Result<GeometryCollection<SRID, P>, PostgisError>
+where
+    Q: PointT<0>,
+    P: PointT<SRID>,
+{
+    let mut geometries = Vec::with_capacity(collection.geometries.len());
+    for (i, geometry) in collection.geometries.iter().enumerate() {
+        geometries
+            .push(geometry_container::<SRID, Q, P>(geometry).map_err(|e| e.nested(i as u32))?);
+    }
+    Ok(GeometryCollection { geometries })
+}
+
+fn geometry_container<const SRID: u32, Q, P>(
+    geometry: &GeometryContainer<0, Q>,
+) -> Result<GeometryContainer<SRID, P>, PostgisError>
+where
+    Q: PointT<0>,
+    P: PointT<SRID>,
+{
+    Ok(match geometry {
+        GeometryContainer::Point(p) => GeometryContainer::Point(point::<SRID, Q, P>(p)?),
+        GeometryContainer::LineString(l) => GeometryContainer::LineString(line_string(l)?),
+        GeometryContainer::Polygon(p) => GeometryContainer::Polygon(polygon(p)?),
+        GeometryContainer::MultiPoint(p) => GeometryContainer::MultiPoint(multi_point(p)?),
+        GeometryContainer::MultiLineString(l) => {
+            GeometryContainer::MultiLineString(multi_line_string(l)?)
+        }
+        GeometryContainer::MultiPolygon(p) => GeometryContainer::MultiPolygon(multi_polygon(p)?),
+        GeometryContainer::GeometryCollection(c) => {
+            GeometryContainer::GeometryCollection(geometry_collection(c)?)
+        }
+    })
+}
+
+macro_rules! impl_point_try_from {
+    ($p:ident) => {
+        impl<const SRID: u32> TryFrom<AnySrid<$p<0>>> for $p<SRID> {
+            type Error = PostgisError;
+
+            fn try_from(value: AnySrid<$p<0>>) -> Result<Self, Self::Error> {
+                point(&check_srid::<SRID, _>(value)?)
+            }
+        }
+    };
+}
+
+impl_point_try_from!(Point);
+impl_point_try_from!(PointZ);
+impl_point_try_from!(PointM);
+impl_point_try_from!(PointZM);
+impl_point_try_from!(PointAny);
+
+macro_rules! impl_geometry_try_from {
+    ($g:ident, $convert:ident) => {
+        impl<const SRID: u32, P, Q> TryFrom<AnySrid<$g<0, Q>>> for $g<SRID, P>
+        where
+            Q: PointT<0>,
+            P: PointT<SRID>,
+        {
+            type Error = PostgisError;
+
+            fn try_from(value: AnySrid<$g<0, Q>>) -> Result<Self, Self::Error> {
+                $convert(&check_srid::<SRID, _>(value)?)
+            }
+        }
+    };
+}
+
+impl_geometry_try_from!(LineString, line_string);
+impl_geometry_try_from!(Polygon, polygon);
+impl_geometry_try_from!(MultiPoint, multi_point);
+impl_geometry_try_from!(MultiLineString, multi_line_string);
+impl_geometry_try_from!(MultiPolygon, multi_polygon);
+impl_geometry_try_from!(GeometryCollection, geometry_collection);
+impl_geometry_try_from!(GeometryContainer, geometry_container);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ewkb::tests::{at, bytes, MULTI_POINT};
+
+    #[test]
+    fn any_srid_test() {
+        let multi_point = bytes(MULTI_POINT);
+        assert_eq!(Ok(Some(4326)), read_srid(&multi_point));
+
+        let geometry: DynGeometry = AnySrid::new(
+            Some(4326),
+            GeometryContainer::read_ewkb(&multi_point, SridPolicy::Any).unwrap(),
+        );
+        let mut out = Vec::new();
+        geometry
+            .geometry
+            .write_ewkb::<LittleEndian, _>(geometry.srid, &mut out)
+            .unwrap();
+        assert_eq!(multi_point, out);
+
+        assert_eq!(
+            Ok(GeometryContainer::MultiPoint(MultiPoint::<
+                4326,
+                Point<4326>,
+            > {
+                points: vec![Point::new(1.0, 2.0), Point::new(3.0, 4.0)],
+            })),
+            GeometryContainer::try_from(geometry.clone())
+        );
+        assert_eq!(
+            Err(PostgisError::SridMismatch {
+                expected: 3857,
+                found: Some(4326),
+                location: at(0, vec![]),
+            }),
+            GeometryContainer::<3857, Point<3857>>::try_from(geometry.clone())
+        );
+        // points which can't be converted are reported with their path
+        let mut any = AnySrid::new(
+            Some(4326),
+            GeometryContainer::<0, PointAny<0>>::GeometryCollection(GeometryCollection {
+                geometries: vec![
+                    GeometryContainer::Point(PointAny::new(1.0, 2.0, None, None)),
+                    GeometryContainer::Point(PointAny::new(1.0, 2.0, Some(3.0), None)),
+                ],
+            }),
+        );
+        let err = GeometryContainer::<4326, Point<4326>>::try_from(any.clone()).unwrap_err();
+        assert!(matches!(err, PostgisError::InvalidGeometry { .. }));
+        assert_eq!(&at(0, vec![1]), err.location());
+        let polygon_any = AnySrid::new(
+            Some(4326),
+            Polygon::<0, PointAny<0>> {
+                rings: vec![
+                    LineString::default(),
+                    LineString {
+                        points: vec![
+                            PointAny::new(1.0, 2.0, None, None),
+                            PointAny::new(1.0, 2.0, Some(3.0), None),
+                        ],
+                    },
+                ],
+            },
+        );
+        let err = Polygon::<4326, Point<4326>>::try_from(polygon_any).unwrap_err();
+        assert_eq!(&at(0, vec![1, 1]), err.location());
+
+        let missing = |expected| PostgisError::SridMismatch {
+            expected,
+            found: None,
+            location: at(0, vec![]),
+        };
+        let line = LineString {
+            points: vec![Point::new(1.0, 2.0), Point::new(3.0, 4.0)],
+        };
+        let polygon = Polygon {
+            rings: vec![line.clone()],
+        };
+        let multi_point = MultiPoint {
+            points: line.points.clone(),
+        };
+        let multi_line = MultiLineString {
+            lines: vec![line.clone()],
+        };
+        let multi_polygon = MultiPolygon {
+            polygons: vec![polygon.clone()],
+        };
+        let collection = GeometryCollection {
+            geometries: vec![GeometryContainer::Point(Point::new(1.0, 2.0))],
+        };
+        assert_eq!(
+            Err(missing(4326)),
+            Point::<4326>::try_from(DynPoint::new(None, Point::new(1.0, 2.0)))
+        );
+        assert_eq!(
+            Err(missing(4326)),
+            PointAny::<4326>::try_from(AnySrid::new(None, PointAny::new(1.0, 2.0, None, None)))
+        );
+        assert_eq!(
+            Err(missing(3857)),
+            LineString::<3857, Point<3857>>::try_from(AnySrid::new(None, line))
+        );
+        assert_eq!(
+            Err(missing(3857)),
+            Polygon::<3857, Point<3857>>::try_from(AnySrid::new(None, polygon))
+        );
+        assert_eq!(
+            Err(missing(3857)),
+            MultiPoint::<3857, Point<3857>>::try_from(AnySrid::new(None, multi_point))
+        );
+        assert_eq!(
+            Err(missing(3857)),
+            MultiLineString::<3857, Point<3857>>::try_from(AnySrid::new(None, multi_line))
+        );
+        assert_eq!(
+            Err(missing(3857)),
+            MultiPolygon::<3857, Point<3857>>::try_from(AnySrid::new(None, multi_polygon))
+        );
+        assert_eq!(
+            Err(missing(3857)),
+            GeometryCollection::<3857, Point<3857>>::try_from(AnySrid::new(None, collection))
+        );
+        any.srid = None;
+        assert_eq!(
+            Err(missing(3857)),
+            GeometryContainer::<3857, PointAny<3857>>::try_from(any)
+        );
+    }
+}
@@ -1,17 +1,23 @@
 use std::fmt;
 
+use crate::types::{Dimension, GeometryType};
+
+#[deprecated(note = "SRID mismatches are reported as `PostgisError::SridMismatch`")]
 #[derive(Debug, Clone, PartialEq)]
 pub struct SRIDError {
     got: Option<u32>,
     want: u32,
 }
 
+#[allow(deprecated)]
 impl SRIDError {
     pub fn new(got: Option<u32>, want: u32) -> Self {
         Self { got, want }
     }
 }
 
+#[deprecated(note = "SRID mismatches are reported as `PostgisError::SridMismatch`")]
+#[allow(deprecated)]
 pub fn check_srid(got: Option<u32>, want: u32) -> Result<(), SRIDError> {
     if got != Some(want) {
         Err(SRIDError::new(got, want))
@@ -20,10 +26,177 @@ pub fn check_srid(got: Option<u32>, want: u32) -> Result<(), SRIDError> {
     }
 }
 
+#[allow(deprecated)]
 impl fmt::Display for SRIDError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Wrong SRID in database: {:?}, Expected: {:?}", self.got, self.want)
     }
 }
 
+#[allow(deprecated)]
 impl std::error::Error for SRIDError {}
+
+/// Error returned when a string is not a valid DE-9IM intersection matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntersectionMatrixError {
+    pub matrix: String,
+}
+
+impl fmt::Display for IntersectionMatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid DE-9IM intersection matrix: {:?}", self.matrix)
+    }
+}
+
+impl std::error::Error for IntersectionMatrixError {}
+
+/// Position of a decoding error: the byte `offset` in the EWKB input and the `path` of indexes of
+/// the nested geometries, rings and points, e.g. `[1, 0]` for the first element of the second
+/// geometry of a collection or `[0, 3]` for the fourth point of the exterior ring of a polygon.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ErrorLocation {
+    pub offset: u64,
+    pub path: Vec<u32>,
+}
+
+/// Error returned when decoding an EWKB geometry fails.
+///
+/// It converts into Diesel's deserialize error, so it can be recovered from a failed query with
+/// `downcast_ref`:
+/// ```
+/// use postgis_diesel::error::PostgisError;
+/// fn wrong_srid(err: &diesel::result::Error) -> Option<u32> {
+///     match err {
+///         diesel::result::Error::DeserializationError(e) => match e.downcast_ref() {
+///             Some(PostgisError::SridMismatch { found, .. }) => *found,
+///             _ => None,
+///         },
+///         _ => None,
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum PostgisError {
+    /// The geometry is of another type than the one it is loaded into.
+    UnexpectedType {
+        expected: GeometryType,
+        found: GeometryType,
+        location: ErrorLocation,
+    },
+    /// The geometry has another SRID than the one it is loaded into, or none.
+    SridMismatch {
+        expected: u32,
+        found: Option<u32>,
+        location: ErrorLocation,
+    },
+    /// A nested geometry has another dimension than its parent. When writing, a point has another
+    /// dimension than the first non-empty point of its geometry.
+    DimensionMismatch {
+        expected: Dimension,
+        found: Dimension,
+        location: ErrorLocation,
+    },
+    /// The input ends in the middle of the geometry.
+    TruncatedInput { location: ErrorLocation },
+    /// The type code is none of the seven geometry types.
+    UnknownTypeCode { code: u32, location: ErrorLocation },
+    /// The geometry can't be built, e.g. a point misses its Z coordinate.
+    InvalidGeometry {
+        reason: String,
+        location: ErrorLocation,
+    },
+    /// Geometry collections are nested deeper than the maximum depth.
+    NestingTooDeep { location: ErrorLocation },
+    /// A number of elements is too large for the rest of the input to hold them.
+    CountTooLarge { count: u32, location: ErrorLocation },
+    /// The input goes on after the end of the geometry.
+    TrailingBytes { location: ErrorLocation },
+}
+
+impl PostgisError {
+    pub fn location(&self) -> &ErrorLocation {
+        match self {
+            PostgisError::UnexpectedType { location, .. }
+            | PostgisError::SridMismatch { location, .. }
+            | PostgisError::DimensionMismatch { location, .. }
+            | PostgisError::TruncatedInput { location }
+            | PostgisError::UnknownTypeCode { location, .. }
+            | PostgisError::InvalidGeometry { location, .. }
+            | PostgisError::NestingTooDeep { location }
+            | PostgisError::CountTooLarge { location, .. }
+            | PostgisError::TrailingBytes { location } => location,
+        }
+    }
+
+    fn location_mut(&mut self) -> &mut ErrorLocation {
+        match self {
+            PostgisError::UnexpectedType { location, .. }
+            | PostgisError::SridMismatch { location, .. }
+            | PostgisError::DimensionMismatch { location, .. }
+            | PostgisError::TruncatedInput { location }
+            | PostgisError::UnknownTypeCode { location, .. }
+            | PostgisError::InvalidGeometry { location, .. }
+            | PostgisError::NestingTooDeep { location }
+            | PostgisError::CountTooLarge { location, .. }
+            | PostgisError::TrailingBytes { location } => location,
+        }
+    }
+
+    /// Prepends the `index` of the nested geometry the error occurred in to its path.
+    pub(crate) fn nested(mut self, index: u32) -> Self {
+        self.location_mut().path.insert(0, index);
+        self
+    }
+}
+
+impl fmt::Display for PostgisError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PostgisError::UnexpectedType {
+                expected, found, ..
+            } => write!(f, "Geometry {:?} is not a {:?}", found, expected)?,
+            PostgisError::SridMismatch {
+                expected, found, ..
+            } => write!(
+                f,
+                "Wrong SRID in database: {:?}, Expected: {:?}",
+                found, expected
+            )?,
+            PostgisError::DimensionMismatch {
+                expected, found, ..
+            } => write!(
+                f,
+                "Nested geometry has dimension {:?}, expected {:?}",
+                found, expected
+            )?,
+            PostgisError::TruncatedInput { location } => {
+                write!(f, "Unexpected end of geometry at byte {}", location.offset)?
+            }
+            PostgisError::UnknownTypeCode { code, .. } => {
+                write!(f, "Unknown geometry type code {}", code)?
+            }
+            PostgisError::InvalidGeometry { reason, .. } => {
+                write!(f, "Invalid geometry: {}", reason)?
+            }
+            PostgisError::NestingTooDeep { .. } => {
+                write!(f, "Geometry collections are nested too deeply")?
+            }
+            PostgisError::CountTooLarge { count, .. } => {
+                write!(f, "Count {} exceeds the size of the geometry", count)?
+            }
+            PostgisError::TrailingBytes { location } => write!(
+                f,
+                "Unexpected bytes after the geometry at byte {}",
+                location.offset
+            )?,
+        }
+        let path = &self.location().path;
+        if !path.is_empty() {
+            write!(f, " (nested geometry {:?})", path)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PostgisError {}
@@ -1,13 +1,19 @@
-use std::io::Cursor;
+use std::{
+    fmt,
+    io::{Cursor, Write},
+};
+
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use diesel::serialize::{self, IsNull};
 
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
-use diesel::{
-    deserialize,
-    pg::Pg,
-    serialize::{self, IsNull, Output},
+use crate::{
+    error::{ErrorLocation, PostgisError},
+    points::Dimension,
+    types::SridPolicy,
 };
 
-#[derive(Debug, PartialEq)]
+/// Type of a geometry as encoded in its EWKB type code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GeometryType {
     Point = 1,
     LineString,
@@ -19,25 +25,27 @@ pub enum GeometryType {
 }
 
 pub const SRID: u32 = 0x20000000;
+/// Mask of the Z, M and SRID flags, the remaining bits hold the geometry type code.
+const FLAGS: u32 = Dimension::ZM as u32 | SRID;
 pub const LITTLE_ENDIAN: u8 = 1;
 pub const BIG_ENDIAN: u8 = 0;
 
 impl From<u32> for GeometryType {
     fn from(t: u32) -> Self {
         if t & 7 == 7 {
-            return Self::GeometryCollection;
+            Self::GeometryCollection
         } else if t & 6 == 6 {
-            return Self::MultiPolygon;
+            Self::MultiPolygon
         } else if t & 5 == 5 {
-            return Self::MultiLineString;
+            Self::MultiLineString
         } else if t & 4 == 4 {
-            return Self::MultiPoint;
+            Self::MultiPoint
         } else if t & 3 == 3 {
-            return Self::Polygon;
+            Self::Polygon
         } else if t & 2 == 2 {
-            return Self::LineString;
+            Self::LineString
         } else {
-            return Self::Point;
+            Self::Point
         }
     }
 }
@@ -46,52 +54,914 @@ pub trait EwkbSerializable {
     fn geometry_type(&self) -> u32;
 }
 
-pub fn write_ewkb_header<T>(
-    geometry: &T,
-    srid: Option<u32>,
-    out: &mut Output<Pg>,
-) -> serialize::Result
+/// Writes the header of `geometry` in the byte order `B`, little endian (NDR) or big endian (XDR).
+pub fn write_ewkb_header<B, T, W>(geometry: &T, srid: Option<u32>, out: &mut W) -> serialize::Result
 where
+    B: byteorder::ByteOrder,
     T: EwkbSerializable,
+    W: Write,
 {
-    out.write_u8(LITTLE_ENDIAN)?;
+    out.write_u8(byte_order::<B>())?;
     let mut p_type = geometry.geometry_type();
     match srid {
         Some(srid) => {
             p_type |= SRID;
-            out.write_u32::<LittleEndian>(p_type)?;
-            out.write_u32::<LittleEndian>(srid)?;
+            out.write_u32::<B>(p_type)?;
+            out.write_u32::<B>(srid)?;
         }
-        None => out.write_u32::<LittleEndian>(p_type)?,
+        None => out.write_u32::<B>(p_type)?,
     }
     Ok(IsNull::No)
 }
 
+/// Writes the header of `geometry` nested in a geometry of dimension `dimension`, which it takes in
+/// place of its own.
+pub fn write_nested_header<B, T, W>(geometry: &T, dimension: u32, out: &mut W) -> serialize::Result
+where
+    B: byteorder::ByteOrder,
+    T: EwkbSerializable,
+    W: Write,
+{
+    out.write_u8(byte_order::<B>())?;
+    out.write_u32::<B>(geometry.geometry_type() & !(Dimension::ZM as u32) | dimension)?;
+    Ok(IsNull::No)
+}
+
+/// Byte order flag of the EWKB written in the byte order `B`.
+fn byte_order<B: byteorder::ByteOrder>() -> u8 {
+    let mut one = [0; 2];
+    B::write_u16(&mut one, 1);
+    if one[0] == 1 {
+        LITTLE_ENDIAN
+    } else {
+        BIG_ENDIAN
+    }
+}
+
+/// Maximum number of nested geometry collections read by default.
+pub const DEFAULT_MAX_DEPTH: u32 = 32;
+
+/// How [`ReadEwkb`] decodes a geometry.
+/// ```
+/// use postgis_diesel::types::{ReadOptions, SridPolicy};
+/// let options = ReadOptions::new(SridPolicy::Strict).with_max_depth(4);
+/// assert_eq!(4, options.max_depth);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ReadOptions {
+    /// How the SRID found is checked against the one of the type.
+    pub policy: SridPolicy,
+    /// Number of nested geometry collections, deeper ones failing with
+    /// [`NestingTooDeep`](PostgisError::NestingTooDeep).
+    pub max_depth: u32,
+    /// Dimension the points are read with instead of their own: missing Z and M are set to 0, or
+    /// NaN for empty points, and the others dropped.
+    pub dimension: Option<Dimension>,
+}
+
+impl ReadOptions {
+    /// Options checking the SRID with `policy` and reading at most [`DEFAULT_MAX_DEPTH`] nested
+    /// collections.
+    pub fn new(policy: SridPolicy) -> Self {
+        Self {
+            policy,
+            max_depth: DEFAULT_MAX_DEPTH,
+            dimension: None,
+        }
+    }
+
+    pub fn with_policy(self, policy: SridPolicy) -> Self {
+        Self { policy, ..self }
+    }
+
+    pub fn with_max_depth(self, max_depth: u32) -> Self {
+        Self { max_depth, ..self }
+    }
+
+    pub fn with_dimension(self, dimension: Dimension) -> Self {
+        Self {
+            dimension: Some(dimension),
+            ..self
+        }
+    }
+}
+
+/// Geometries decoded from EWKB, the SRID found being checked against the one of the type with
+/// `policy`.
+pub trait ReadEwkb: Sized {
+    fn read_ewkb(bytes: &[u8], policy: SridPolicy) -> Result<Self, PostgisError> {
+        Self::read_ewkb_with(bytes, ReadOptions::new(policy))
+    }
+
+    /// Like [`read_ewkb`](ReadEwkb::read_ewkb) with all the `options`.
+    fn read_ewkb_with(bytes: &[u8], options: ReadOptions) -> Result<Self, PostgisError>;
+}
+
+/// Decodes the geometry `bytes` with `read`, failing if bytes are left after it.
+pub fn read_all<G>(
+    bytes: &[u8],
+    read: impl FnOnce(&mut Cursor<&[u8]>) -> Result<G, PostgisError>,
+) -> Result<G, PostgisError> {
+    let mut cursor = Cursor::new(bytes);
+    let geometry = read(&mut cursor)?;
+    let offset = cursor.position();
+    if offset < bytes.len() as u64 {
+        return Err(PostgisError::TrailingBytes {
+            location: location(offset),
+        });
+    }
+    Ok(geometry)
+}
+
+/// Minimal size of a geometry nested in a multi geometry or a collection: byte order, type code
+/// and a count or two coordinates.
+pub const NESTED_GEOMETRY_MIN_SIZE: u64 = 9;
+
+/// Size of the coordinates of a point of type `g_type`.
+pub fn coordinates_size(g_type: u32) -> u64 {
+    match Dimension::from(g_type) {
+        Dimension::None => 16,
+        Dimension::Z | Dimension::M => 24,
+        Dimension::ZM => 32,
+    }
+}
+
+/// Reads a number of elements taking at least `min_size` bytes each, failing when the rest of the
+/// input can't hold them. Counts can thus be trusted to preallocate.
+pub fn read_count<T>(cursor: &mut Cursor<&[u8]>, min_size: u64) -> Result<u32, PostgisError>
+where
+    T: byteorder::ByteOrder,
+{
+    let offset = cursor.position();
+    let count = read_u32::<T>(cursor)?;
+    let remaining = (cursor.get_ref().len() as u64).saturating_sub(cursor.position());
+    if u64::from(count) * min_size > remaining {
+        return Err(PostgisError::CountTooLarge {
+            count,
+            location: location(offset),
+        });
+    }
+    Ok(count)
+}
+
+/// Checks that a collection with its type code at `offset` may be read with `depth` levels of
+/// collections left, returning the levels left for its geometries.
+pub fn check_depth(depth: u32, offset: u64) -> Result<u32, PostgisError> {
+    depth
+        .checked_sub(1)
+        .ok_or_else(|| PostgisError::NestingTooDeep {
+            location: location(offset),
+        })
+}
+
+/// Geometries encoded as EWKB with a header carrying `srid`, in the byte order `B`: `LittleEndian`
+/// (NDR) as sent to the database, or `BigEndian` (XDR). Both are re-exported from the `byteorder`
+/// crate by [`types`](crate::types).
+/// ```
+/// use postgis_diesel::types::{BigEndian, LittleEndian, Point, WriteEwkb};
+///
+/// let point = Point::<4326>::new(1.0, 2.0);
+/// let (mut ndr, mut xdr) = (Vec::new(), Vec::new());
+/// point.write_ewkb::<LittleEndian, _>(Some(4326), &mut ndr).unwrap();
+/// point.write_ewkb::<BigEndian, _>(Some(4326), &mut xdr).unwrap();
+/// assert_eq!(&[1, 1, 0, 0, 0x20], &ndr[..5]);
+/// assert_eq!(&[0, 0x20, 0, 0, 1], &xdr[..5]);
+/// ```
+pub trait WriteEwkb {
+    fn write_ewkb<B, W>(&self, srid: Option<u32>, out: &mut W) -> serialize::Result
+    where
+        B: byteorder::ByteOrder,
+        W: Write;
+}
+
 pub struct EwkbHeader {
     pub g_type: u32,
     pub srid: Option<u32>,
+    /// Offset of the type code in the input.
+    pub offset: u64,
+}
+
+impl EwkbHeader {
+    pub fn check_srid(&self, srid: u32, policy: SridPolicy) -> Result<(), PostgisError> {
+        if !policy.accepts(self.srid, srid) {
+            return Err(PostgisError::SridMismatch {
+                expected: srid,
+                found: self.srid,
+                location: location(self.offset),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn location(offset: u64) -> ErrorLocation {
+    ErrorLocation {
+        offset,
+        path: Vec::new(),
+    }
+}
+
+fn truncated<E>(offset: u64) -> impl FnOnce(E) -> PostgisError {
+    move |_| PostgisError::TruncatedInput {
+        location: location(offset),
+    }
+}
+
+pub fn read_u8(cursor: &mut Cursor<&[u8]>) -> Result<u8, PostgisError> {
+    let offset = cursor.position();
+    cursor.read_u8().map_err(truncated(offset))
+}
+
+pub fn read_u32<T>(cursor: &mut Cursor<&[u8]>) -> Result<u32, PostgisError>
+where
+    T: byteorder::ByteOrder,
+{
+    let offset = cursor.position();
+    cursor.read_u32::<T>().map_err(truncated(offset))
+}
+
+pub fn read_f64<T>(cursor: &mut Cursor<&[u8]>) -> Result<f64, PostgisError>
+where
+    T: byteorder::ByteOrder,
+{
+    let offset = cursor.position();
+    cursor.read_f64::<T>().map_err(truncated(offset))
+}
+
+/// Turns the error of building a geometry, e.g. a point from its coordinates, into a
+/// [`PostgisError::InvalidGeometry`] at `offset`.
+pub fn invalid_geometry<E: fmt::Display>(offset: u64) -> impl FnOnce(E) -> PostgisError {
+    move |e| PostgisError::InvalidGeometry {
+        reason: e.to_string(),
+        location: location(offset),
+    }
 }
 
 pub fn read_ewkb_header<T>(
     expected_type: GeometryType,
     cursor: &mut Cursor<&[u8]>,
-) -> deserialize::Result<EwkbHeader>
+) -> Result<EwkbHeader, PostgisError>
 where
     T: byteorder::ByteOrder,
 {
-    let g_type = cursor.read_u32::<T>()?;
-    if GeometryType::from(g_type) != expected_type {
-        return Err(format!(
-            "Geometry {:?} is not a {:?}",
-            GeometryType::from(g_type),
-            expected_type
-        )
-        .into());
+    let offset = cursor.position();
+    let g_type = read_u32::<T>(cursor)?;
+    let geometry_type = read_geometry_type(g_type, offset)?;
+    if geometry_type != expected_type {
+        return Err(PostgisError::UnexpectedType {
+            expected: expected_type,
+            found: geometry_type,
+            location: location(offset),
+        });
     }
     let mut srid = None;
     // SRID included
     if g_type & SRID == SRID {
-        srid = Some(cursor.read_u32::<T>()?);
+        srid = Some(read_u32::<T>(cursor)?);
+    }
+    Ok(EwkbHeader {
+        g_type,
+        srid,
+        offset,
+    })
+}
+
+/// Returns the SRID in the header of the EWKB geometry `bytes`, without decoding the geometry.
+pub fn read_srid(bytes: &[u8]) -> Result<Option<u32>, PostgisError> {
+    let mut cursor = Cursor::new(bytes);
+    if read_u8(&mut cursor)? == BIG_ENDIAN {
+        read_header_srid::<BigEndian>(&mut cursor)
+    } else {
+        read_header_srid::<LittleEndian>(&mut cursor)
+    }
+}
+
+fn read_header_srid<T>(cursor: &mut Cursor<&[u8]>) -> Result<Option<u32>, PostgisError>
+where
+    T: byteorder::ByteOrder,
+{
+    let g_type = read_u32::<T>(cursor)?;
+    if g_type & SRID == SRID {
+        Ok(Some(read_u32::<T>(cursor)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Returns the type of the geometry from its type code read at `offset`, failing for codes other
+/// than the seven geometry types.
+pub fn read_geometry_type(g_type: u32, offset: u64) -> Result<GeometryType, PostgisError> {
+    match g_type & !FLAGS {
+        1..=7 => Ok(GeometryType::from(g_type & !FLAGS)),
+        code => Err(PostgisError::UnknownTypeCode {
+            code,
+            location: location(offset),
+        }),
+    }
+}
+
+/// Reads the header of a geometry nested in a multi geometry or a collection of type
+/// `parent_type`, after its byte order. Nested geometries must have the dimension of their parent
+/// and take its SRID, an SRID of their own is skipped like PostGIS does.
+pub fn read_nested_header<T>(
+    expected_type: Option<GeometryType>,
+    parent_type: u32,
+    cursor: &mut Cursor<&[u8]>,
+) -> Result<EwkbHeader, PostgisError>
+where
+    T: byteorder::ByteOrder,
+{
+    let offset = cursor.position();
+    let g_type = read_u32::<T>(cursor)?;
+    let geometry_type = read_geometry_type(g_type, offset)?;
+    if let Some(expected_type) = expected_type {
+        if geometry_type != expected_type {
+            return Err(PostgisError::UnexpectedType {
+                expected: expected_type,
+                found: geometry_type,
+                location: location(offset),
+            });
+        }
+    }
+    let (expected, found) = (Dimension::from(parent_type), Dimension::from(g_type));
+    if expected != found {
+        return Err(PostgisError::DimensionMismatch {
+            expected,
+            found,
+            location: location(offset),
+        });
+    }
+    let mut srid = None;
+    if g_type & SRID == SRID {
+        srid = Some(read_u32::<T>(cursor)?);
+    }
+    Ok(EwkbHeader {
+        g_type,
+        srid,
+        offset,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Cursor;
+
+    use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+
+    use super::{GeometryType, ReadEwkb, ReadOptions, WriteEwkb, LITTLE_ENDIAN};
+    use crate::{
+        error::{ErrorLocation, PostgisError},
+        geometry_container::read_geometry_container,
+        geometrycollection::write_geometry_collection,
+        multiline::write_multiline,
+        multipoint::write_multi_point,
+        points::{write_point, Dimension},
+        types::*,
+    };
+
+    // ST_AsEWKB('SRID=4326;MULTIPOINT(1 2,3 4)')
+    pub(crate) const MULTI_POINT: &str = "0104000020e6100000020000000101000000000000000000f03f000000000000004001010000000000000000000840\
+                                          0000000000001040";
+    // ST_AsEWKB('SRID=4326;MULTILINESTRING((0 0,1 1),(2 2,3 3))')
+    const MULTI_LINE: &str = "0105000020e61000000200000001020000000200000000000000000000000000000000000000000000000000f03f00\
+                              0000000000f03f010200000002000000000000000000004000000000000000400000000000000840000000000000\
+                              0840";
+    // ST_AsEWKB('SRID=4326;GEOMETRYCOLLECTION(POINT(1 2),MULTIPOINT(3 4))')
+    pub(crate) const COLLECTION: &str = "0107000020e6100000020000000101000000000000000000f03f00000000000000400104000000010000000101000000\
+                                         00000000000008400000000000001040";
+
+    pub(crate) fn bytes(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn try_read<const SRID: u32>(
+        bytes: &[u8],
+    ) -> Result<GeometryContainer<SRID, Point<SRID>>, PostgisError> {
+        let mut cursor = Cursor::new(bytes);
+        assert_eq!(LITTLE_ENDIAN, cursor.read_u8().unwrap());
+        read_geometry_container::<SRID, LittleEndian, Point<SRID>>(
+            &mut cursor,
+            ReadOptions::new(SridPolicy::Strict),
+        )
+    }
+
+    fn read(bytes: &[u8]) -> GeometryContainer<4326, Point<4326>> {
+        try_read(bytes).unwrap()
+    }
+
+    pub(crate) fn at(offset: u64, path: Vec<u32>) -> ErrorLocation {
+        ErrorLocation { offset, path }
+    }
+
+    fn collection() -> GeometryCollection<4326, Point<4326>> {
+        GeometryCollection {
+            geometries: vec![
+                GeometryContainer::Point(Point::new(1.0, 2.0)),
+                GeometryContainer::MultiPoint(MultiPoint {
+                    points: vec![Point::new(3.0, 4.0)],
+                }),
+            ],
+        }
+    }
+
+    /// A point in a collection in a collection in a collection.
+    pub(crate) fn nested_collection() -> GeometryCollection<4326, Point<4326>> {
+        let innermost = GeometryCollection {
+            geometries: vec![GeometryContainer::Point(Point::new(1.0, 2.0))],
+        };
+        let middle = GeometryCollection {
+            geometries: vec![
+                GeometryContainer::GeometryCollection(innermost),
+                GeometryContainer::Point(Point::new(3.0, 4.0)),
+            ],
+        };
+        GeometryCollection {
+            geometries: vec![GeometryContainer::GeometryCollection(middle)],
+        }
+    }
+
+    #[test]
+    fn nested_geometries_without_srid_test() {
+        let multi_point = MultiPoint::<4326, Point<4326>> {
+            points: vec![Point::new(1.0, 2.0), Point::new(3.0, 4.0)],
+        };
+        let mut out = Vec::new();
+        write_multi_point::<4326, LittleEndian, _, _>(&multi_point, &mut out).unwrap();
+        assert_eq!(bytes(MULTI_POINT), out);
+        assert_eq!(GeometryContainer::MultiPoint(multi_point), read(&out));
+
+        let multi_line = MultiLineString::<4326, Point<4326>> {
+            lines: vec![
+                LineString {
+                    points: vec![Point::new(0.0, 0.0), Point::new(1.0, 1.0)],
+                },
+                LineString {
+                    points: vec![Point::new(2.0, 2.0), Point::new(3.0, 3.0)],
+                },
+            ],
+        };
+        let mut out = Vec::new();
+        write_multiline::<4326, LittleEndian, _, _>(&multi_line, &mut out).unwrap();
+        assert_eq!(bytes(MULTI_LINE), out);
+        assert_eq!(GeometryContainer::MultiLineString(multi_line), read(&out));
+
+        let mut out = Vec::new();
+        write_geometry_collection::<4326, LittleEndian, _, _>(&collection(), &mut out).unwrap();
+        assert_eq!(bytes(COLLECTION), out);
+        assert_eq!(
+            GeometryContainer::GeometryCollection(collection()),
+            read(&out)
+        );
+    }
+
+    #[test]
+    fn nested_byte_order_test() {
+        // little endian collection holding a big endian point and a big endian multi point of a
+        // little endian point
+        let mixed = bytes(
+            "0107000020e61000000200000000000000013ff0000000000000400000000000000000000000040000000101010000\
+             0000000000000008400000000000001040",
+        );
+        assert_eq!(
+            GeometryContainer::GeometryCollection(collection()),
+            read(&mixed)
+        );
+    }
+
+    #[test]
+    fn nested_type_code_test() {
+        // the first point of the multi point is replaced with a circular string (type 8)
+        let mut multi_point = bytes(MULTI_POINT);
+        multi_point[14] = 8;
+        assert_eq!(
+            Err(PostgisError::UnknownTypeCode {
+                code: 8,
+                location: at(14, vec![0]),
+            }),
+            try_read::<4326>(&multi_point)
+        );
+        // and with a line string
+        multi_point[14] = 2;
+        assert_eq!(
+            Err(PostgisError::UnexpectedType {
+                expected: GeometryType::Point,
+                found: GeometryType::LineString,
+                location: at(14, vec![0]),
+            }),
+            try_read::<4326>(&multi_point)
+        );
+    }
+
+    #[test]
+    fn errors_test() {
+        let mut multi_point = bytes(MULTI_POINT);
+        assert_eq!(
+            Err(PostgisError::SridMismatch {
+                expected: 3857,
+                found: Some(4326),
+                location: at(1, vec![]),
+            }),
+            try_read::<3857>(&multi_point)
+        );
+        assert_eq!(
+            Err(PostgisError::CountTooLarge {
+                count: 2,
+                location: at(9, vec![]),
+            }),
+            try_read::<4326>(&multi_point[..40])
+        );
+        // in the Y of the point, first geometry of the collection
+        assert_eq!(
+            Err(PostgisError::TruncatedInput {
+                location: at(26, vec![0]),
+            }),
+            try_read::<4326>(&bytes(COLLECTION)[..32])
+        );
+        // Z flag on the first point only
+        multi_point[17] = 0x80;
+        assert_eq!(
+            Err(PostgisError::DimensionMismatch {
+                expected: Dimension::None,
+                found: Dimension::Z,
+                location: at(14, vec![0]),
+            }),
+            try_read::<4326>(&multi_point)
+        );
+
+        // the point in the multi point, second geometry of the collection
+        let mut collection = bytes(COLLECTION);
+        collection[44] = 8;
+        let err = try_read::<4326>(&collection).unwrap_err();
+        assert_eq!(&at(44, vec![1, 0]), err.location());
+        assert_eq!(
+            "Unknown geometry type code 8 (nested geometry [1, 0])",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn ring_and_point_paths_test() {
+        // the first point of the exterior ring of a Z polygon can't be read as a 2D point
+        let mut polygon_z = Polygon::<4326, PointZ<4326>>::new();
+        polygon_z.add_points(&[
+            PointZ::new(0.0, 0.0, 1.0),
+            PointZ::new(0.0, 1.0, 1.0),
+            PointZ::new(1.0, 1.0, 1.0),
+            PointZ::new(0.0, 0.0, 1.0),
+        ]);
+        let mut out = Vec::new();
+        polygon_z
+            .write_ewkb::<LittleEndian, _>(Some(4326), &mut out)
+            .unwrap();
+        let err = try_read::<4326>(&out).unwrap_err();
+        assert!(matches!(err, PostgisError::InvalidGeometry { .. }));
+        assert_eq!(&at(17, vec![0, 0]), err.location());
+
+        // the points of the interior ring are cut off
+        let mut polygon = Polygon::<4326, Point<4326>>::new();
+        let ring = [
+            Point::new(0.0, 0.0),
+            Point::new(0.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 0.0),
+        ];
+        polygon.add_points(&ring).add_ring().add_points(&ring);
+        let mut out = Vec::new();
+        polygon
+            .write_ewkb::<LittleEndian, _>(Some(4326), &mut out)
+            .unwrap();
+        assert_eq!(
+            Err(PostgisError::CountTooLarge {
+                count: 4,
+                location: at(81, vec![1]),
+            }),
+            try_read::<4326>(&out[..100])
+        );
+
+        // and of a Z line string
+        let line_z = LineString::<4326, PointZ<4326>> {
+            points: vec![PointZ::new(0.0, 0.0, 1.0), PointZ::new(1.0, 1.0, 1.0)],
+        };
+        let mut out = Vec::new();
+        line_z
+            .write_ewkb::<LittleEndian, _>(Some(4326), &mut out)
+            .unwrap();
+        let err = try_read::<4326>(&out).unwrap_err();
+        assert_eq!(&at(13, vec![0]), err.location());
+    }
+
+    #[test]
+    fn srid_policy_test() {
+        let multi_point = MultiPoint::<4326, Point<4326>> {
+            points: vec![Point::new(1.0, 2.0), Point::new(3.0, 4.0)],
+        };
+        // SRID flag cleared and SRID removed
+        let mut missing = bytes(MULTI_POINT);
+        missing[4] = 0;
+        missing.drain(5..9);
+        // SRID present but 0
+        let mut zero = bytes(MULTI_POINT);
+        zero[5..9].copy_from_slice(&[0; 4]);
+
+        let read = |bytes: &[u8], policy| MultiPoint::<4326, Point<4326>>::read_ewkb(bytes, policy);
+        assert_eq!(
+            Err(PostgisError::SridMismatch {
+                expected: 4326,
+                found: None,
+                location: at(1, vec![]),
+            }),
+            read(&missing, SridPolicy::Strict)
+        );
+        assert_eq!(
+            Ok(multi_point.clone()),
+            read(&missing, SridPolicy::AcceptMissing)
+        );
+        assert_eq!(
+            Ok(multi_point.clone()),
+            read(&missing, SridPolicy::ZeroIsUnknown)
+        );
+
+        assert!(read(&zero, SridPolicy::Strict).is_err());
+        assert!(read(&zero, SridPolicy::AcceptMissing).is_err());
+        assert_eq!(
+            Ok(multi_point.clone()),
+            read(&zero, SridPolicy::ZeroIsUnknown)
+        );
+
+        let wrong = bytes(MULTI_POINT);
+        assert!(
+            MultiPoint::<3857, Point<3857>>::read_ewkb(&wrong, SridPolicy::ZeroIsUnknown).is_err()
+        );
+
+        let read =
+            |bytes: &[u8], policy| GeometryContainer::<4326, Point<4326>>::read_ewkb(bytes, policy);
+        assert_eq!(
+            Err(PostgisError::SridMismatch {
+                expected: 4326,
+                found: None,
+                location: at(1, vec![]),
+            }),
+            read(&missing, SridPolicy::Strict)
+        );
+        assert_eq!(
+            Ok(GeometryContainer::MultiPoint(multi_point)),
+            read(&missing, SridPolicy::AcceptMissing)
+        );
+    }
+
+    #[test]
+    fn empty_geometries_test() {
+        // ST_AsEWKB('SRID=4326;POINT EMPTY')
+        let empty_point = bytes("0101000020e6100000000000000000f87f000000000000f87f");
+        let mut out = Vec::new();
+        write_point::<4326, LittleEndian, _, _>(&Point::<4326>::empty(), &mut out).unwrap();
+        assert_eq!(empty_point, out);
+        let read = Point::<4326>::read_ewkb(&empty_point, SridPolicy::Strict).unwrap();
+        assert!(read.is_empty());
+        assert!(!Point::<4326>::new(1.0, 2.0).is_empty());
+
+        // ST_AsEWKB('SRID=4326;LINESTRING Z EMPTY')
+        let line = LineString::<4326, PointZ<4326>> { points: vec![] };
+        assert!(line.is_empty());
+        assert_eq!(Dimension::Z as u32, line.dimension());
+        let mut out = Vec::new();
+        line.write_ewkb::<LittleEndian, _>(Some(4326), &mut out)
+            .unwrap();
+        assert_eq!(bytes("01020000a0e610000000000000"), out);
+
+        let multi_point = MultiPoint::<4326, PointM<4326>> {
+            points: vec![PointM::empty()],
+        };
+        assert!(multi_point.is_empty());
+        assert_eq!(Dimension::M as u32, multi_point.dimension());
+        let collection = GeometryCollection::<4326, PointZM<4326>> {
+            geometries: vec![
+                GeometryContainer::Polygon(Polygon { rings: vec![] }),
+                GeometryContainer::LineString(LineString {
+                    points: vec![PointZM::new(1.0, 2.0, 3.0, 4.0)],
+                }),
+            ],
+        };
+        assert!(!collection.is_empty());
+        assert_eq!(Dimension::ZM as u32, collection.dimension());
+    }
+
+    #[test]
+    fn nested_collections_test() {
+        let outer = nested_collection();
+        let mut out = Vec::new();
+        write_geometry_collection::<4326, LittleEndian, _, _>(&outer, &mut out).unwrap();
+        assert_eq!(
+            Ok(outer.clone()),
+            GeometryCollection::read_ewkb(&out, SridPolicy::Strict)
+        );
+        let options = ReadOptions::new(SridPolicy::Strict);
+        assert_eq!(
+            Ok(outer.clone()),
+            GeometryCollection::read_ewkb_with(&out, options.with_max_depth(3))
+        );
+        assert_eq!(
+            Err(PostgisError::NestingTooDeep {
+                location: at(23, vec![0, 0]),
+            }),
+            GeometryCollection::<4326, Point<4326>>::read_ewkb_with(
+                &out,
+                options.with_max_depth(2)
+            )
+        );
+        assert_eq!(
+            Err(PostgisError::NestingTooDeep {
+                location: at(1, vec![]),
+            }),
+            GeometryContainer::<4326, Point<4326>>::read_ewkb_with(&out, options.with_max_depth(0))
+        );
+
+        // a collection in a collection in ... fails instead of overflowing the stack
+        let mut deep = Vec::new();
+        for _ in 0..100_000 {
+            deep.extend_from_slice(&bytes("010700000001000000"));
+        }
+        let err =
+            GeometryContainer::<4326, Point<4326>>::read_ewkb(&deep, SridPolicy::Any).unwrap_err();
+        assert!(matches!(err, PostgisError::NestingTooDeep { .. }));
+        assert_eq!(32, err.location().path.len());
+        let err =
+            Force3DZ::<GeometryContainer<4326, PointZ<4326>>>::read_ewkb(&deep, SridPolicy::Any)
+                .unwrap_err();
+        assert!(matches!(err, PostgisError::NestingTooDeep { .. }));
+
+        // only collections count as a level, not multi geometries
+        let mut deepest = deep[..32 * 9].to_vec();
+        deepest.extend_from_slice(&bytes("0104000000010000000101000000"));
+        deepest.extend_from_slice(&[0; 16]);
+        assert!(
+            GeometryContainer::<4326, Point<4326>>::read_ewkb(&deepest, SridPolicy::Any).is_ok()
+        );
+        assert!(
+            Force3DZ::<GeometryContainer<4326, PointZ<4326>>>::read_ewkb(&deepest, SridPolicy::Any)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn untrusted_input_test() {
+        // a huge count is rejected before allocating
+        let mut multi_point = bytes(MULTI_POINT);
+        multi_point[9..13].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            Err(PostgisError::CountTooLarge {
+                count: u32::MAX,
+                location: at(9, vec![]),
+            }),
+            try_read::<4326>(&multi_point)
+        );
+        // and so is a huge number of rings or points in a ring
+        let polygon = Polygon::<4326, Point<4326>> {
+            rings: vec![LineString {
+                points: vec![
+                    Point::new(0.0, 0.0),
+                    Point::new(1.0, 0.0),
+                    Point::new(0.0, 1.0),
+                    Point::new(0.0, 0.0),
+                ],
+            }],
+        };
+        let mut out = Vec::new();
+        polygon
+            .write_ewkb::<LittleEndian, _>(Some(4326), &mut out)
+            .unwrap();
+        assert_eq!(
+            Ok(polygon.clone()),
+            Polygon::read_ewkb(&out, SridPolicy::Strict)
+        );
+        let mut rings = out.clone();
+        rings[9..13].copy_from_slice(&0x4000_0000u32.to_le_bytes());
+        assert!(matches!(
+            Polygon::<4326, Point<4326>>::read_ewkb(&rings, SridPolicy::Strict),
+            Err(PostgisError::CountTooLarge { .. })
+        ));
+        let mut points = out.clone();
+        points[13..17].copy_from_slice(&5u32.to_le_bytes());
+        assert_eq!(
+            Err(PostgisError::CountTooLarge {
+                count: 5,
+                location: at(13, vec![0]),
+            }),
+            Polygon::<4326, Point<4326>>::read_ewkb(&points, SridPolicy::Strict)
+        );
+
+        // trailing garbage
+        let mut trailing = bytes(MULTI_POINT);
+        let end = trailing.len() as u64;
+        trailing.push(0);
+        assert_eq!(
+            Err(PostgisError::TrailingBytes {
+                location: at(end, vec![]),
+            }),
+            MultiPoint::<4326, Point<4326>>::read_ewkb(&trailing, SridPolicy::Strict)
+        );
+        assert_eq!(
+            Err(PostgisError::TrailingBytes {
+                location: at(end, vec![]),
+            }),
+            Force3DZ::<MultiPoint<4326, PointZ<4326>>>::read_ewkb(&trailing, SridPolicy::Strict)
+                .map(Force3DZ::into_inner)
+        );
+        assert!(Point::<4326>::read_ewkb(&bytes(MULTI_POINT)[..34], SridPolicy::Any).is_err());
+    }
+
+    #[test]
+    fn mutated_input_test() {
+        // what the fuzz target does, on truncated and altered golden inputs
+        let decode = |bytes: &[u8]| {
+            let _ = GeometryContainer::<0, PointAny<0>>::read_ewkb(bytes, SridPolicy::Any);
+            let _ = Force2D::<GeometryContainer<0, PointAny<0>>>::read_ewkb(bytes, SridPolicy::Any);
+            let _ =
+                Force3DZ::<GeometryContainer<0, PointAny<0>>>::read_ewkb(bytes, SridPolicy::Any);
+            let _ =
+                Force3DM::<GeometryContainer<0, PointAny<0>>>::read_ewkb(bytes, SridPolicy::Any);
+            let _ = Force4D::<GeometryContainer<0, PointAny<0>>>::read_ewkb(bytes, SridPolicy::Any);
+        };
+        for hex in [MULTI_POINT, MULTI_LINE, COLLECTION] {
+            let input = bytes(hex);
+            for len in 0..input.len() {
+                decode(&input[..len]);
+            }
+            for i in 0..input.len() {
+                for value in [0, 1, 0x7f, 0x80, 0xff] {
+                    let mut mutated = input.clone();
+                    mutated[i] = value;
+                    decode(&mutated);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn big_endian_test() {
+        // ST_AsEWKB('SRID=4326;MULTIPOINT(1 2,3 4)', 'XDR')
+        let xdr = bytes(
+            "0020000004000010e60000000200000000013ff00000000000004000000000000000\
+             000000000140080000000000004010000000000000",
+        );
+        let multi_point = MultiPoint::<4326, Point<4326>> {
+            points: vec![Point::new(1.0, 2.0), Point::new(3.0, 4.0)],
+        };
+        let mut out = Vec::new();
+        multi_point
+            .write_ewkb::<BigEndian, _>(Some(4326), &mut out)
+            .unwrap();
+        assert_eq!(xdr, out);
+        assert_eq!(
+            Ok(multi_point),
+            MultiPoint::read_ewkb(&out, SridPolicy::Strict)
+        );
+
+        // the same writers in both byte orders for every geometry type
+        let collection = GeometryCollection::<4326, PointZM<4326>> {
+            geometries: vec![
+                GeometryContainer::Point(PointZM::new(1.0, 2.0, 3.0, 4.0)),
+                GeometryContainer::LineString(LineString {
+                    points: vec![PointZM::new(1.0, 2.0, 3.0, 4.0); 2],
+                }),
+                GeometryContainer::Polygon(Polygon {
+                    rings: vec![LineString {
+                        points: vec![PointZM::new(0.0, 0.0, 0.0, 0.0); 4],
+                    }],
+                }),
+                GeometryContainer::MultiLineString(MultiLineString {
+                    lines: vec![LineString {
+                        points: vec![PointZM::new(5.0, 6.0, 7.0, 8.0); 2],
+                    }],
+                }),
+                GeometryContainer::MultiPolygon(MultiPolygon { polygons: vec![] }),
+                GeometryContainer::GeometryCollection(GeometryCollection {
+                    geometries: vec![GeometryContainer::MultiPoint(MultiPoint {
+                        points: vec![PointZM::new(1.0, 2.0, 3.0, 4.0)],
+                    })],
+                }),
+            ],
+        };
+        let (mut ndr, mut xdr) = (Vec::new(), Vec::new());
+        collection
+            .write_ewkb::<LittleEndian, _>(Some(4326), &mut ndr)
+            .unwrap();
+        collection
+            .write_ewkb::<BigEndian, _>(Some(4326), &mut xdr)
+            .unwrap();
+        assert_eq!(ndr.len(), xdr.len());
+        assert_eq!(super::BIG_ENDIAN, xdr[0]);
+        assert_eq!(
+            Ok(collection.clone()),
+            GeometryCollection::read_ewkb(&ndr, SridPolicy::Strict)
+        );
+        assert_eq!(
+            Ok(collection),
+            GeometryCollection::read_ewkb(&xdr, SridPolicy::Strict)
+        );
     }
-    Ok(EwkbHeader { g_type, srid })
 }
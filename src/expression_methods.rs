@@ -0,0 +1,119 @@
+//! Methods available on geometry, geography, box and text expressions.
+//!
+//! Casts keep the nullability of the casted expression, so a `Nullable<Geometry>` column becomes a
+//! `Nullable<Geography>` expression. The casts from text can fail and diesel only derives casts
+//! between nullable types from infallible ones, so they take non-null `Text` expressions only.
+//! ```
+//! #[macro_use] extern crate diesel;
+//! use diesel::{debug_query, pg::Pg, prelude::*};
+//! use postgis_diesel::{expression_methods::GeometryExpressionMethods, functions::st_area_geography};
+//!
+//! table! {
+//!     use postgis_diesel::sql_types::*;
+//!     use diesel::sql_types::*;
+//!     areas (id) {
+//!         id -> Int4,
+//!         polygon -> Geometry,
+//!     }
+//! }
+//!
+//! let query = areas::table.select(st_area_geography(areas::polygon.to_geography(), true));
+//! assert_eq!(
+//!     debug_query::<Pg, _>(&query).to_string(),
+//!     "SELECT ST_Area(CAST(\"areas\".\"polygon\" AS geography), $1) FROM \"areas\" -- binds: [true]"
+//! );
+//! ```
+use diesel::{
+    dsl,
+    expression::FallibleCastsTo,
+    sql_types::{MaybeNullableType, SingleValue, SqlType, Text},
+    Expression, ExpressionMethods,
+};
+
+use crate::sql_types::{Box2D, Box3D, Geography, Geometry};
+
+/// SQL types which cast to `ST`, keeping their nullability.
+pub trait CastWithNullability<ST>: SqlType {
+    /// `ST`, nullable if `Self` is nullable.
+    type Out: SingleValue;
+
+    fn cast<E>(expr: E) -> dsl::Cast<E, Self::Out>
+    where
+        E: ExpressionMethods + Expression<SqlType = Self>;
+}
+
+impl<T, ST> CastWithNullability<ST> for T
+where
+    T: SqlType + FallibleCastsTo<<T::IsNull as MaybeNullableType<ST>>::Out>,
+    T::IsNull: MaybeNullableType<ST>,
+    <T::IsNull as MaybeNullableType<ST>>::Out: SingleValue,
+{
+    type Out = <T::IsNull as MaybeNullableType<ST>>::Out;
+
+    fn cast<E>(expr: E) -> dsl::Cast<E, Self::Out>
+    where
+        E: ExpressionMethods + Expression<SqlType = Self>,
+    {
+        expr.fallible_cast()
+    }
+}
+
+type CastOut<E, ST> = <<E as Expression>::SqlType as CastWithNullability<ST>>::Out;
+
+/// The return type of [`to_geometry()`](GeometryExpressionMethods::to_geometry).
+pub type ToGeometry<E> = dsl::Cast<E, CastOut<E, Geometry>>;
+/// The return type of [`to_geography()`](GeometryExpressionMethods::to_geography).
+pub type ToGeography<E> = dsl::Cast<E, CastOut<E, Geography>>;
+/// The return type of [`to_box2d()`](GeometryExpressionMethods::to_box2d).
+pub type ToBox2D<E> = dsl::Cast<E, CastOut<E, Box2D>>;
+/// The return type of [`to_box3d()`](GeometryExpressionMethods::to_box3d).
+pub type ToBox3D<E> = dsl::Cast<E, CastOut<E, Box3D>>;
+/// The return type of [`to_text()`](GeometryExpressionMethods::to_text).
+pub type ToText<E> = dsl::Cast<E, CastOut<E, Text>>;
+
+pub trait GeometryExpressionMethods: ExpressionMethods {
+    /// Renders `CAST(expr AS geometry)`, available for geography, box2d, box3d and non-null text
+    /// expressions.
+    fn to_geometry(self) -> ToGeometry<Self>
+    where
+        Self::SqlType: CastWithNullability<Geometry>,
+    {
+        <Self::SqlType as CastWithNullability<Geometry>>::cast(self)
+    }
+
+    /// Renders `CAST(expr AS geography)`, available for geometry and non-null text expressions.
+    /// The database rejects geometries whose SRID is not a lon/lat one.
+    fn to_geography(self) -> ToGeography<Self>
+    where
+        Self::SqlType: CastWithNullability<Geography>,
+    {
+        <Self::SqlType as CastWithNullability<Geography>>::cast(self)
+    }
+
+    /// Renders `CAST(expr AS box2d)`, available for geometry, box3d and non-null text expressions.
+    fn to_box2d(self) -> ToBox2D<Self>
+    where
+        Self::SqlType: CastWithNullability<Box2D>,
+    {
+        <Self::SqlType as CastWithNullability<Box2D>>::cast(self)
+    }
+
+    /// Renders `CAST(expr AS box3d)`, available for geometry, box2d and non-null text expressions.
+    fn to_box3d(self) -> ToBox3D<Self>
+    where
+        Self::SqlType: CastWithNullability<Box3D>,
+    {
+        <Self::SqlType as CastWithNullability<Box3D>>::cast(self)
+    }
+
+    /// Renders `CAST(expr AS text)`, available for geometry, geography, box2d and box3d expressions.
+    /// Geometries and geographies become hex encoded EWKB, boxes `BOX(xmin ymin,xmax ymax)`.
+    fn to_text(self) -> ToText<Self>
+    where
+        Self::SqlType: CastWithNullability<Text>,
+    {
+        <Self::SqlType as CastWithNullability<Text>>::cast(self)
+    }
+}
+
+impl<T: ExpressionMethods> GeometryExpressionMethods for T {}
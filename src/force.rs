@@ -0,0 +1,187 @@
+use std::{fmt::Debug, ops::Deref};
+
+use diesel::{
+    deserialize::{self, FromSql},
+    pg::{self, Pg},
+    serialize::{self, Output, ToSql},
+};
+
+use crate::{
+    error::PostgisError,
+    ewkb::{ReadEwkb, ReadOptions},
+    points::Dimension,
+    sql_types::Geometry,
+    types::{Force2D, Force3DM, Force3DZ, Force4D, SridPolicy},
+};
+
+macro_rules! impl_force {
+    ($f:ident, $dimension:expr) => {
+        impl<G> $f<G> {
+            pub fn new(geometry: G) -> Self {
+                Self { geometry }
+            }
+
+            pub fn into_inner(self) -> G {
+                self.geometry
+            }
+        }
+
+        impl<G> From<G> for $f<G> {
+            fn from(geometry: G) -> Self {
+                Self::new(geometry)
+            }
+        }
+
+        impl<G> Deref for $f<G> {
+            type Target = G;
+
+            fn deref(&self) -> &G {
+                &self.geometry
+            }
+        }
+
+        impl<G> FromSql<Geometry, Pg> for $f<G>
+        where
+            G: ReadEwkb,
+        {
+            fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
+                Ok(Self::read_ewkb(bytes.as_bytes(), SridPolicy::Strict)?)
+            }
+        }
+
+        impl<G> ReadEwkb for $f<G>
+        where
+            G: ReadEwkb,
+        {
+            fn read_ewkb_with(bytes: &[u8], options: ReadOptions) -> Result<Self, PostgisError> {
+                Ok(Self::new(G::read_ewkb_with(
+                    bytes,
+                    options.with_dimension($dimension),
+                )?))
+            }
+        }
+
+        impl<G> ToSql<Geometry, Pg> for $f<G>
+        where
+            G: ToSql<Geometry, Pg> + Debug,
+        {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                self.geometry.to_sql(out)
+            }
+        }
+    };
+}
+
+impl_force!(Force2D, Dimension::None);
+impl_force!(Force3DZ, Dimension::Z);
+impl_force!(Force3DM, Dimension::M);
+impl_force!(Force4D, Dimension::ZM);
+
+#[cfg(test)]
+mod tests {
+    use byteorder::LittleEndian;
+
+    use super::*;
+    use crate::{
+        ewkb::{
+            tests::{at, bytes, COLLECTION},
+            WriteEwkb,
+        },
+        types::*,
+    };
+
+    #[test]
+    fn force_dimension_test() {
+        let line = LineString::<4326, PointZ<4326>> {
+            points: vec![PointZ::new(1.0, 2.0, 3.0), PointZ::new(4.0, 5.0, 6.0)],
+        };
+        let mut out = Vec::new();
+        line.write_ewkb::<LittleEndian, _>(Some(4326), &mut out)
+            .unwrap();
+
+        assert_eq!(
+            Ok(LineString::<4326, Point<4326>> {
+                points: vec![Point::new(1.0, 2.0), Point::new(4.0, 5.0)],
+            }),
+            Force2D::read_ewkb(&out, SridPolicy::Strict).map(Force2D::into_inner)
+        );
+        assert_eq!(
+            Ok(LineString::<4326, PointZM<4326>> {
+                points: vec![
+                    PointZM::new(1.0, 2.0, 3.0, 0.0),
+                    PointZM::new(4.0, 5.0, 6.0, 0.0)
+                ],
+            }),
+            Force4D::read_ewkb(&out, SridPolicy::Strict).map(Force4D::into_inner)
+        );
+        assert_eq!(
+            Ok(line.clone()),
+            Force3DZ::read_ewkb(&out, SridPolicy::Strict).map(Force3DZ::into_inner)
+        );
+        let any = Force3DM::<LineString<4326, PointAny<4326>>>::read_ewkb(&out, SridPolicy::Strict)
+            .unwrap();
+        assert_eq!(PointAny::new(1.0, 2.0, None, Some(0.0)), any.points[0]);
+
+        // the SRID policy of the inner type is kept
+        let mut missing = Vec::new();
+        line.write_ewkb::<LittleEndian, _>(None, &mut missing)
+            .unwrap();
+        assert!(
+            Force2D::<LineString<4326, Point<4326>>>::read_ewkb(&missing, SridPolicy::Strict)
+                .is_err()
+        );
+        assert!(
+            Force2D::<Lenient<LineString<4326, Point<4326>>>>::read_ewkb(
+                &missing,
+                SridPolicy::Strict
+            )
+            .is_ok()
+        );
+        let dyn_line =
+            Force2D::<AnySrid<LineString<0, Point<0>>>>::read_ewkb(&out, SridPolicy::Strict)
+                .unwrap();
+        assert_eq!(Some(4326), dyn_line.srid);
+
+        // nested geometries are coerced too
+        let collection = bytes(COLLECTION);
+        assert_eq!(
+            Ok(GeometryCollection::<4326, PointM<4326>> {
+                geometries: vec![
+                    GeometryContainer::Point(PointM::new(1.0, 2.0, 0.0)),
+                    GeometryContainer::MultiPoint(MultiPoint {
+                        points: vec![PointM::new(3.0, 4.0, 0.0)],
+                    }),
+                ],
+            }),
+            Force3DM::read_ewkb(&collection, SridPolicy::Strict).map(Force3DM::into_inner)
+        );
+        let read = |bytes: &[u8]| {
+            Force2D::<GeometryCollection<4326, Point<4326>>>::read_ewkb(bytes, SridPolicy::Strict)
+        };
+        assert_eq!(
+            Err(PostgisError::CountTooLarge {
+                count: 1,
+                location: at(39, vec![1]),
+            }),
+            read(&collection[..50])
+        );
+        assert_eq!(
+            Err(PostgisError::TruncatedInput {
+                location: at(26, vec![0]),
+            }),
+            read(&collection[..32])
+        );
+
+        // the coordinates added to an empty point are empty too
+        let mut empty_point = Vec::new();
+        Point::<4326>::empty()
+            .write_ewkb::<LittleEndian, _>(Some(4326), &mut empty_point)
+            .unwrap();
+        assert!(
+            Force3DZ::<PointZ<4326>>::read_ewkb(&empty_point, SridPolicy::Strict)
+                .unwrap()
+                .z
+                .is_nan()
+        );
+    }
+}
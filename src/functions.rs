@@ -0,0 +1,580 @@
+//! Typed bindings for PostGIS SQL functions.
+//!
+//! Functions which PostGIS overloads for `geography` are exposed twice: the plain name takes
+//! [`Geometry`] and the `_geography` variant takes [`Geography`](crate::sql_types::Geography) together with `use_spheroid`.
+//! Geometry arguments accept the same [`Operand`]s as the operators: expressions of either
+//! nullability and values such as `Point` or `Option<Point>`. The result is nullable if one of
+//! them is.
+//! ```
+//! #[macro_use] extern crate diesel;
+//! use diesel::prelude::*;
+//! use postgis_diesel::{functions::*, gps::Polygon};
+//! table! {
+//!     use postgis_diesel::sql_types::*;
+//!     use diesel::sql_types::*;
+//!     regions (id) {
+//!         id -> Int4,
+//!         polygon -> Geometry,
+//!     }
+//! }
+//! #[derive(Queryable)]
+//! struct RegionArea {
+//!     polygon: Polygon,
+//!     area: f64,
+//!     perimeter: f64,
+//! }
+//! let query = regions::table.select((
+//!     regions::polygon,
+//!     st_area(regions::polygon),
+//!     st_perimeter(regions::polygon),
+//! ));
+//! ```
+use diesel::{
+    dsl,
+    expression::{AppearsOnTable, AsExpression, Expression, SelectableExpression, ValidGrouping},
+    pg::Pg,
+    query_builder::{AstPass, QueryFragment, QueryId},
+    sql_types::{Array, Binary, Bool, Double, Integer, Jsonb, Text},
+    ExpressionMethods, QueryResult,
+};
+
+use crate::operators::{Operand, ValueOperand};
+use crate::sql_types::{
+    Box2D, Box3D, CombinedNullableValue, GeographyOrNullable, Geometry, GeometryDumpRecord,
+    GeometryOrNullable, GridCellRecord, MaybeNullableValue,
+};
+
+/// Declares the functions taking geometries with `define_sql_function!` in the `sql` module and
+/// wraps each of them in a public function of the same name. The wrapper takes every `G`, `G1`
+/// and `G2` argument as an [`Operand`] of [`Geometry`], which picks `Geometry` for plain values
+/// such as `Point` and keeps the SQL type of expressions, nullable or not. The helper type of
+/// the same name takes the argument types like `define_sql_function!`, followed by the markers
+/// of the nullable geometry arguments.
+macro_rules! geometry_functions {
+    (@wrap $meta:tt [$($param:tt)*] [$($marker:tt)*] [$($bound:tt)*] [$($call:tt)*] [$($sql_type:tt)*] [$($expr:tt)*] $arg:ident: G, $($rest:tt)*) => {
+        geometry_functions!(@geometry $meta [$($param)*] [$($marker)*] [$($bound)*] [$($call)*] [$($sql_type)*] [$($expr)*] $arg G M, $($rest)*);
+    };
+    (@wrap $meta:tt [$($param:tt)*] [$($marker:tt)*] [$($bound:tt)*] [$($call:tt)*] [$($sql_type:tt)*] [$($expr:tt)*] $arg:ident: G1, $($rest:tt)*) => {
+        geometry_functions!(@geometry $meta [$($param)*] [$($marker)*] [$($bound)*] [$($call)*] [$($sql_type)*] [$($expr)*] $arg G1 M1, $($rest)*);
+    };
+    (@wrap $meta:tt [$($param:tt)*] [$($marker:tt)*] [$($bound:tt)*] [$($call:tt)*] [$($sql_type:tt)*] [$($expr:tt)*] $arg:ident: G2, $($rest:tt)*) => {
+        geometry_functions!(@geometry $meta [$($param)*] [$($marker)*] [$($bound)*] [$($call)*] [$($sql_type)*] [$($expr)*] $arg G2 M2, $($rest)*);
+    };
+    (@wrap $meta:tt [$($param:tt)*] [$($marker:tt)*] [$($bound:tt)*] [$($call:tt)*] [$($sql_type:tt)*] [$($expr:tt)*] $arg:ident: $ty:ident $(<$ty_param:ident>)?, $($rest:tt)*) => {
+        geometry_functions!(
+            @wrap $meta
+            [$($param)* $arg,]
+            [$($marker)*]
+            [$($bound)* $arg: AsExpression<$ty $(<$ty_param>)?>,]
+            [$($call)* $arg,]
+            [$($sql_type)*]
+            [$($expr)* $arg,]
+            $($rest)*
+        );
+    };
+    (@wrap {$(#[doc = $doc:literal])* $name:ident [$($generics:tt)*]} [$($param:ident,)*] [$($marker:ident,)*] [$($bound:tt)*] [$($call:tt)*] [$($sql_type:tt)*] [$($expr:tt)*]) => {
+        $(#[doc = $doc])*
+        #[allow(non_camel_case_types)]
+        pub fn $name<$($generics)* $($param,)* $($marker,)*>($($param: $param),*) -> $name<$($param,)* $($marker,)*>
+        where
+            $($bound)*
+        {
+            sql::$name($($call)*)
+        }
+
+        #[allow(non_camel_case_types)]
+        pub type $name<$($param,)* $($marker = ValueOperand,)*> = sql::$name<$($sql_type)* $($expr)*>;
+    };
+    (@geometry $meta:tt [$($param:tt)*] [$($marker:tt)*] [$($bound:tt)*] [$($call:tt)*] [$($sql_type:tt)*] [$($expr:tt)*] $arg:ident $g:ident $m:ident, $($rest:tt)*) => {
+        geometry_functions!(
+            @wrap $meta
+            [$($param)* $arg,]
+            [$($marker)* $m,]
+            [$($bound)* $arg: Operand<Geometry, $m>, <$arg as Operand<Geometry, $m>>::Expression: Expression<SqlType = $g>,]
+            [$($call)* $arg.into_operand(),]
+            [$($sql_type)* dsl::SqlTypeOf<<$arg as Operand<Geometry, $m>>::Expression>,]
+            [$($expr)* <$arg as Operand<Geometry, $m>>::Expression,]
+            $($rest)*
+        );
+    };
+    ($(
+        $(#[doc = $doc:literal])*
+        #[sql_name = $sql_name:literal]
+        $(#[$kind:ident])*
+        fn $name:ident<$($g:ident: $g_bound:ident $(+ $more:path)?),+>($($arg:ident: $arg_ty:ident $(<$arg_ty_param:ident>)?),+) -> $ret:ty;
+    )+) => {
+        mod sql {
+            use super::*;
+
+            $(
+                define_sql_function! {
+                    #[sql_name = $sql_name]
+                    $(#[$kind])*
+                    fn $name<$($g: $g_bound $(+ $more)?),+>($($arg: $arg_ty $(<$arg_ty_param>)?),+) -> $ret;
+                }
+            )+
+        }
+
+        $(
+            geometry_functions!(
+                @wrap {$(#[doc = $doc])* $name [$($g: $g_bound $(+ $more)?,)+]}
+                [] [] [] [] [] []
+                $($arg: $arg_ty $(<$arg_ty_param>)?,)+
+            );
+        )+
+    };
+}
+
+geometry_functions! {
+    /// Returns the area of a polygonal geometry.
+    #[sql_name = "ST_Area"]
+    fn st_area<G: GeometryOrNullable + MaybeNullableValue<Double>>(geom: G) -> G::Out;
+
+    /// Returns the 2D Cartesian length of a linear geometry.
+    #[sql_name = "ST_Length"]
+    fn st_length<G: GeometryOrNullable + MaybeNullableValue<Double>>(geom: G) -> G::Out;
+
+    /// Returns the 2D perimeter of a polygonal geometry.
+    #[sql_name = "ST_Perimeter"]
+    fn st_perimeter<G: GeometryOrNullable + MaybeNullableValue<Double>>(geom: G) -> G::Out;
+
+    /// Returns the minimum 2D Cartesian distance between two geometries in projected units.
+    #[sql_name = "ST_Distance"]
+    fn st_distance<G1: GeometryOrNullable + CombinedNullableValue<G2, Double>, G2: GeometryOrNullable>(geom1: G1, geom2: G2) -> G1::Out;
+
+    /// Returns the minimum 3D Cartesian distance between two geometries in projected units.
+    #[sql_name = "ST_3DDistance"]
+    fn st_3d_distance<G1: GeometryOrNullable + CombinedNullableValue<G2, Double>, G2: GeometryOrNullable>(geom1: G1, geom2: G2) -> G1::Out;
+
+    /// Returns the Hausdorff distance between two geometries, a measure of how similar or dissimilar they are.
+    #[sql_name = "ST_HausdorffDistance"]
+    fn st_hausdorff_distance<G1: GeometryOrNullable + CombinedNullableValue<G2, Double>, G2: GeometryOrNullable>(geom1: G1, geom2: G2) -> G1::Out;
+
+    /// Returns the Hausdorff distance between two geometries, densifying each segment into `densify_frac` equal parts first.
+    #[sql_name = "ST_HausdorffDistance"]
+    fn st_hausdorff_distance_densify<G1: GeometryOrNullable + CombinedNullableValue<G2, Double>, G2: GeometryOrNullable>(geom1: G1, geom2: G2, densify_frac: Double) -> G1::Out;
+
+    /// Creates a LineString connecting the vertices of two point, linestring or multipoint geometries.
+    #[sql_name = "ST_MakeLine"]
+    fn st_make_line<G1: GeometryOrNullable + CombinedNullableValue<G2, Geometry>, G2: GeometryOrNullable>(geom1: G1, geom2: G2) -> G1::Out;
+
+    /// Creates a Polygon formed by the given closed LineString shell.
+    #[sql_name = "ST_MakePolygon"]
+    fn st_make_polygon<G: GeometryOrNullable + MaybeNullableValue<Geometry>>(linestring: G) -> G::Out;
+
+    /// Creates a Polygon formed by the given closed LineString shell and an array of closed LineString holes.
+    #[sql_name = "ST_MakePolygon"]
+    fn st_make_polygon_with_holes<G: GeometryOrNullable + MaybeNullableValue<Geometry>>(outer: G, interiors: Array<Geometry>) -> G::Out;
+
+    /// Sets the SRID on a geometry without transforming its coordinates.
+    #[sql_name = "ST_SetSRID"]
+    fn st_set_srid<G: GeometryOrNullable + MaybeNullableValue<Geometry>>(geom: G, srid: Integer) -> G::Out;
+
+    /// Forces the geometry into 2-dimensional mode, dropping Z and M coordinates.
+    #[sql_name = "ST_Force2D"]
+    fn st_force_2d<G: GeometryOrNullable + MaybeNullableValue<Geometry>>(geom: G) -> G::Out;
+
+    /// Forces the geometry into XYZ mode, missing Z coordinates are set to 0.
+    #[sql_name = "ST_Force3D"]
+    fn st_force_3d<G: GeometryOrNullable + MaybeNullableValue<Geometry>>(geom: G) -> G::Out;
+
+    /// Returns the geometry with the vertex order reversed.
+    #[sql_name = "ST_Reverse"]
+    fn st_reverse<G: GeometryOrNullable + MaybeNullableValue<Geometry>>(geom: G) -> G::Out;
+
+    /// Returns the X coordinate of a point.
+    #[sql_name = "ST_X"]
+    fn st_x<G: GeometryOrNullable + MaybeNullableValue<Double>>(point: G) -> G::Out;
+
+    /// Returns the Y coordinate of a point.
+    #[sql_name = "ST_Y"]
+    fn st_y<G: GeometryOrNullable + MaybeNullableValue<Double>>(point: G) -> G::Out;
+
+    /// Returns the Z coordinate of a point, or NULL if the point has no Z dimension.
+    #[sql_name = "ST_Z"]
+    fn st_z<G: GeometryOrNullable>(point: G) -> Nullable<Double>;
+
+    /// Returns the M coordinate of a point, or NULL if the point has no M dimension.
+    #[sql_name = "ST_M"]
+    fn st_m<G: GeometryOrNullable>(point: G) -> Nullable<Double>;
+
+    /// Returns the spatial reference identifier of a geometry.
+    #[sql_name = "ST_SRID"]
+    fn st_srid<G: GeometryOrNullable + MaybeNullableValue<Integer>>(geom: G) -> G::Out;
+
+    /// Returns the number of points (vertices) in a geometry.
+    #[sql_name = "ST_NPoints"]
+    fn st_npoints<G: GeometryOrNullable + MaybeNullableValue<Integer>>(geom: G) -> G::Out;
+
+    /// Returns the type of the geometry as a string, e.g. `ST_LineString` or `ST_Polygon`.
+    #[sql_name = "ST_GeometryType"]
+    fn st_geometry_type<G: GeometryOrNullable + MaybeNullableValue<Text>>(geom: G) -> G::Out;
+
+    /// Returns true if the geometry is an empty geometry.
+    #[sql_name = "ST_IsEmpty"]
+    fn st_is_empty<G: GeometryOrNullable + MaybeNullableValue<Bool>>(geom: G) -> G::Out;
+
+    /// Returns true if the geometry is well-formed and valid in 2D according to the OGC rules.
+    #[sql_name = "ST_IsValid"]
+    fn st_is_valid<G: GeometryOrNullable + MaybeNullableValue<Bool>>(geom: G) -> G::Out;
+
+    /// Returns a geometry covering all points within the given distance from the input geometry.
+    #[sql_name = "ST_Buffer"]
+    fn st_buffer<G: GeometryOrNullable + MaybeNullableValue<Geometry>>(geom: G, radius: Double) -> G::Out;
+
+    /// Returns a buffer of the geometry tuned by blank-separated `key=value` parameters, e.g. `quad_segs=8 endcap=flat`.
+    #[sql_name = "ST_Buffer"]
+    fn st_buffer_with_params<G: GeometryOrNullable + MaybeNullableValue<Geometry>>(geom: G, radius: Double, buffer_style_parameters: Text) -> G::Out;
+
+    /// Returns a geometry representing the point-set intersection of two geometries.
+    #[sql_name = "ST_Intersection"]
+    fn st_intersection<G1: GeometryOrNullable + CombinedNullableValue<G2, Geometry>, G2: GeometryOrNullable>(geom1: G1, geom2: G2) -> G1::Out;
+
+    /// Returns a geometry representing the point-set union of two geometries.
+    #[sql_name = "ST_Union"]
+    fn st_union<G1: GeometryOrNullable + CombinedNullableValue<G2, Geometry>, G2: GeometryOrNullable>(geom1: G1, geom2: G2) -> G1::Out;
+
+    /// Returns a geometry representing the part of geometry A that does not intersect geometry B.
+    #[sql_name = "ST_Difference"]
+    fn st_difference<G1: GeometryOrNullable + CombinedNullableValue<G2, Geometry>, G2: GeometryOrNullable>(geom1: G1, geom2: G2) -> G1::Out;
+
+    /// Returns a simplified version of the geometry using the Douglas-Peucker algorithm.
+    #[sql_name = "ST_Simplify"]
+    fn st_simplify<G: GeometryOrNullable + MaybeNullableValue<Geometry>>(geom: G, tolerance: Double) -> G::Out;
+
+    /// Returns the geometric center of a geometry.
+    #[sql_name = "ST_Centroid"]
+    fn st_centroid<G: GeometryOrNullable + MaybeNullableValue<Geometry>>(geom: G) -> G::Out;
+
+    /// Returns the smallest convex geometry that encloses all geometries in the input.
+    #[sql_name = "ST_ConvexHull"]
+    fn st_convex_hull<G: GeometryOrNullable + MaybeNullableValue<Geometry>>(geom: G) -> G::Out;
+
+    /// Aggregate which returns the point-set union of the geometries of a group.
+    #[sql_name = "ST_Union"]
+    #[aggregate]
+    fn st_union_agg<G: GeometryOrNullable>(geom: G) -> Nullable<Geometry>;
+
+    /// Aggregate which collects the geometries of a group into a multi geometry or a GeometryCollection without changing them.
+    #[sql_name = "ST_Collect"]
+    #[aggregate]
+    fn st_collect_agg<G: GeometryOrNullable>(geom: G) -> Nullable<Geometry>;
+
+    /// Aggregate which returns the 2D bounding box enclosing the geometries of a group.
+    #[sql_name = "ST_Extent"]
+    #[aggregate]
+    fn st_extent<G: GeometryOrNullable>(geom: G) -> Nullable<Box2D>;
+
+    /// Aggregate which returns the 3D bounding box enclosing the geometries of a group.
+    #[sql_name = "ST_3DExtent"]
+    #[aggregate]
+    fn st_3d_extent<G: GeometryOrNullable>(geom: G) -> Nullable<Box3D>;
+
+    /// Aggregate which creates a LineString from the point, linestring or multipoint geometries of a group.
+    #[sql_name = "ST_MakeLine"]
+    #[aggregate]
+    fn st_make_line_agg<G: GeometryOrNullable>(geom: G) -> Nullable<Geometry>;
+
+    /// Returns the OGC Well-Known Text representation of the geometry, without SRID.
+    #[sql_name = "ST_AsText"]
+    fn st_as_text<G: GeometryOrNullable + MaybeNullableValue<Text>>(geom: G) -> G::Out;
+
+    /// Returns the OGC Well-Known Text representation of the geometry with at most `maxdecimaldigits` digits after the decimal point.
+    #[sql_name = "ST_AsText"]
+    fn st_as_text_with_digits<G: GeometryOrNullable + MaybeNullableValue<Text>>(geom: G, maxdecimaldigits: Integer) -> G::Out;
+
+    /// Returns the Well-Known Text representation of the geometry prefixed with its SRID.
+    #[sql_name = "ST_AsEWKT"]
+    fn st_as_ewkt<G: GeometryOrNullable + MaybeNullableValue<Text>>(geom: G) -> G::Out;
+
+    /// Returns the geometry as a GeoJSON geometry object.
+    #[sql_name = "ST_AsGeoJSON"]
+    fn st_as_geojson<G: GeometryOrNullable + MaybeNullableValue<Text>>(geom: G) -> G::Out;
+
+    /// Returns the geometry as a GeoJSON geometry object with at most `maxdecimaldigits` digits after the decimal point.
+    #[sql_name = "ST_AsGeoJSON"]
+    fn st_as_geojson_with_digits<G: GeometryOrNullable + MaybeNullableValue<Text>>(geom: G, maxdecimaldigits: Integer) -> G::Out;
+
+    /// Returns the geometry as a GeoJSON geometry object, `options` is a bitmask adding the bounding box (1), the short (2) or long (4) CRS.
+    #[sql_name = "ST_AsGeoJSON"]
+    fn st_as_geojson_with_options<G: GeometryOrNullable + MaybeNullableValue<Text>>(geom: G, maxdecimaldigits: Integer, options: Integer) -> G::Out;
+
+    /// Returns the OGC Well-Known Binary representation of the geometry, without SRID.
+    #[sql_name = "ST_AsBinary"]
+    fn st_as_binary<G: GeometryOrNullable + MaybeNullableValue<Binary>>(geom: G) -> G::Out;
+
+    /// Returns the Extended Well-Known Binary representation of the geometry, with SRID.
+    #[sql_name = "ST_AsEWKB"]
+    fn st_as_ewkb<G: GeometryOrNullable + MaybeNullableValue<Binary>>(geom: G) -> G::Out;
+
+    /// Returns the Extended Well-Known Binary representation of the geometry, with SRID, using the `NDR` (little-endian) or `XDR` (big-endian) byte order.
+    #[sql_name = "ST_AsEWKB"]
+    fn st_as_ewkb_with_endian<G: GeometryOrNullable + MaybeNullableValue<Binary>>(geom: G, endian: Text) -> G::Out;
+
+    /// Returns the OGC Well-Known Binary representation of the geometry using the `NDR` (little-endian) or `XDR` (big-endian) byte order.
+    #[sql_name = "ST_AsBinary"]
+    fn st_as_binary_with_endian<G: GeometryOrNullable + MaybeNullableValue<Binary>>(geom: G, endian: Text) -> G::Out;
+
+    /// Returns the geometry in TWKB (Tiny Well-Known Binary) format, coordinates are rounded to `prec` decimal digits.
+    #[sql_name = "ST_AsTWKB"]
+    fn st_as_twkb<G: GeometryOrNullable + MaybeNullableValue<Binary>>(geom: G, prec: Integer) -> G::Out;
+
+    /// Transforms the geometry into the coordinate space of a Mapbox Vector Tile covering `bounds`, returns NULL if nothing is left after clipping.
+    #[sql_name = "ST_AsMVTGeom"]
+    fn st_as_mvt_geom<G: GeometryOrNullable>(geom: G, bounds: Box2D) -> Nullable<Geometry>;
+
+    /// Transforms the geometry into the coordinate space of a Mapbox Vector Tile of `extent` units covering `bounds`, clipping it `buffer` units outside the tile if `clip_geom` is true.
+    #[sql_name = "ST_AsMVTGeom"]
+    fn st_as_mvt_geom_with_options<G: GeometryOrNullable>(geom: G, bounds: Box2D, extent: Integer, buffer: Integer, clip_geom: Bool) -> Nullable<Geometry>;
+
+    /// Set-returning function which extracts the components of a geometry, one `geometry_dump` row per component.
+    #[sql_name = "ST_Dump"]
+    fn st_dump<G: GeometryOrNullable>(geom: G) -> GeometryDumpRecord;
+
+    /// Set-returning function which extracts the points of a geometry, one `geometry_dump` row per point.
+    #[sql_name = "ST_DumpPoints"]
+    fn st_dump_points<G: GeometryOrNullable>(geom: G) -> GeometryDumpRecord;
+
+    /// Set-returning function which extracts the rings of a polygon as polygons, the exterior ring has path `{0}`.
+    #[sql_name = "ST_DumpRings"]
+    fn st_dump_rings<G: GeometryOrNullable>(polygon: G) -> GeometryDumpRecord;
+
+    /// Set-returning function which divides a geometry into parts having at most `max_vertices` vertices.
+    #[sql_name = "ST_Subdivide"]
+    fn st_subdivide<G: GeometryOrNullable>(geom: G, max_vertices: Integer) -> Geometry;
+
+    /// Window function which returns the DBSCAN cluster id of each geometry, NULL for geometries which are in no cluster.
+    #[sql_name = "ST_ClusterDBSCAN"]
+    #[window]
+    fn st_cluster_dbscan<G: GeometryOrNullable>(geom: G, eps: Double, minpoints: Integer) -> Nullable<Integer>;
+
+    /// Window function which returns the k-means cluster id of each geometry, NULL for empty geometries.
+    #[sql_name = "ST_ClusterKMeans"]
+    #[window]
+    fn st_cluster_kmeans<G: GeometryOrNullable>(geom: G, number_of_clusters: Integer) -> Nullable<Integer>;
+
+    /// Window function which returns the id of the cluster of geometries separated by no more than `distance` each geometry belongs to, requires PostGIS 3.4.
+    #[sql_name = "ST_ClusterWithinWin"]
+    #[window]
+    fn st_cluster_within_win<G: GeometryOrNullable>(geom: G, distance: Double) -> Nullable<Integer>;
+
+    /// Aggregate which returns an array of GeometryCollections, one per cluster of geometries separated by no more than `distance`.
+    #[sql_name = "ST_ClusterWithin"]
+    #[aggregate]
+    fn st_cluster_within<G: GeometryOrNullable>(geom: G, distance: Double) -> Nullable<Array<Geometry>>;
+
+    /// Set-returning function which returns the hexagons with edge `size` covering the bounds of `bounds`.
+    #[sql_name = "ST_HexagonGrid"]
+    fn st_hexagon_grid<G: GeometryOrNullable>(size: Double, bounds: G) -> GridCellRecord;
+
+    /// Set-returning function which returns the squares with edge `size` covering the bounds of `bounds`.
+    #[sql_name = "ST_SquareGrid"]
+    fn st_square_grid<G: GeometryOrNullable>(size: Double, bounds: G) -> GridCellRecord;
+
+    /// Returns the DE-9IM intersection matrix of two geometries, load it into [`IntersectionMatrix`](crate::types::IntersectionMatrix).
+    #[sql_name = "ST_Relate"]
+    fn st_relate<G1: GeometryOrNullable + CombinedNullableValue<G2, Text>, G2: GeometryOrNullable>(geom_a: G1, geom_b: G2) -> G1::Out;
+
+    /// Returns true if the DE-9IM intersection matrix of two geometries matches `intersection_matrix_pattern`.
+    #[sql_name = "ST_Relate"]
+    fn st_relate_pattern<G1: GeometryOrNullable + CombinedNullableValue<G2, Bool>, G2: GeometryOrNullable>(geom_a: G1, geom_b: G2, intersection_matrix_pattern: Text) -> G1::Out;
+
+    /// Returns true if the geometries are within `distance` of each other, in units of the SRID.
+    #[sql_name = "ST_DWithin"]
+    fn st_d_within<G1: GeometryOrNullable + CombinedNullableValue<G2, Bool>, G2: GeometryOrNullable>(g1: G1, g2: G2, distance: Double) -> G1::Out;
+}
+
+define_sql_function! {
+    /// Returns the area of a polygonal geography in square meters, computed on the spheroid if `use_spheroid` is true and on a sphere otherwise.
+    #[sql_name = "ST_Area"]
+    fn st_area_geography<G: GeographyOrNullable + MaybeNullableValue<Double>>(geog: G, use_spheroid: Bool) -> G::Out;
+}
+
+define_sql_function! {
+    /// Returns the length of a linear geography in meters, computed on the spheroid if `use_spheroid` is true and on a sphere otherwise.
+    #[sql_name = "ST_Length"]
+    fn st_length_geography<G: GeographyOrNullable + MaybeNullableValue<Double>>(geog: G, use_spheroid: Bool) -> G::Out;
+}
+
+define_sql_function! {
+    /// Returns the perimeter of a polygonal geography in meters, computed on the spheroid if `use_spheroid` is true and on a sphere otherwise.
+    #[sql_name = "ST_Perimeter"]
+    fn st_perimeter_geography<G: GeographyOrNullable + MaybeNullableValue<Double>>(geog: G, use_spheroid: Bool) -> G::Out;
+}
+
+define_sql_function! {
+    /// Returns the minimum geodesic distance between two geographies in meters, computed on the spheroid if `use_spheroid` is true and on a sphere otherwise.
+    #[sql_name = "ST_Distance"]
+    fn st_distance_geography<G1: GeographyOrNullable + CombinedNullableValue<G2, Double>, G2: GeographyOrNullable>(geog1: G1, geog2: G2, use_spheroid: Bool) -> G1::Out;
+}
+
+define_sql_function! {
+    /// Creates a 2D point geometry with an unknown SRID.
+    #[sql_name = "ST_MakePoint"]
+    fn st_make_point(x: Double, y: Double) -> Geometry;
+}
+
+define_sql_function! {
+    /// Creates a 3D point geometry with an unknown SRID.
+    #[sql_name = "ST_MakePoint"]
+    fn st_make_point_z(x: Double, y: Double, z: Double) -> Geometry;
+}
+
+define_sql_function! {
+    /// Creates a point geometry with an M coordinate and an unknown SRID.
+    #[sql_name = "ST_MakePointM"]
+    fn st_make_point_m(x: Double, y: Double, m: Double) -> Geometry;
+}
+
+define_sql_function! {
+    /// Creates a 4D point geometry with an unknown SRID.
+    #[sql_name = "ST_MakePoint"]
+    fn st_make_point_zm(x: Double, y: Double, z: Double, m: Double) -> Geometry;
+}
+
+define_sql_function! {
+    /// Creates a rectangular polygon from the minimum and maximum values for X and Y in the given SRID.
+    #[sql_name = "ST_MakeEnvelope"]
+    fn st_make_envelope(xmin: Double, ymin: Double, xmax: Double, ymax: Double, srid: Integer) -> Geometry;
+}
+
+define_sql_function! {
+    /// Creates a LineString connecting the vertices of an array of point, linestring or multipoint geometries.
+    #[sql_name = "ST_MakeLine"]
+    fn st_make_line_array(geoms: Array<Geometry>) -> Geometry;
+}
+
+define_sql_function! {
+    /// Creates a geometry from its Extended Well-Known Binary representation, in either byte order.
+    #[sql_name = "ST_GeomFromEWKB"]
+    fn st_geom_from_ewkb(ewkb: Binary) -> Geometry;
+}
+
+define_sql_function! {
+    /// Creates a rectangular polygon in Web Mercator (SRID 3857) giving the extent of the tile `x`, `y` on zoom level `zoom`.
+    #[sql_name = "ST_TileEnvelope"]
+    fn st_tile_envelope(zoom: Integer, x: Integer, y: Integer) -> Geometry;
+}
+
+/// Casts the text produced by the `st_as_geojson` functions to `jsonb`.
+pub fn geojson_to_jsonb<E>(geojson: E) -> dsl::Cast<E, Jsonb>
+where
+    E: Expression<SqlType = Text>,
+{
+    geojson.fallible_cast()
+}
+
+define_sql_function! {
+    /// Returns the hexagon with edge `size` at column `cell_i` and row `cell_j` of the grid starting at `POINT(0 0)`.
+    #[sql_name = "ST_Hexagon"]
+    fn st_hexagon(size: Double, cell_i: Integer, cell_j: Integer) -> Geometry;
+}
+
+define_sql_function! {
+    /// Returns the hexagon with edge `size` at column `cell_i` and row `cell_j` of the grid starting at `origin`.
+    #[sql_name = "ST_Hexagon"]
+    fn st_hexagon_with_origin(size: Double, cell_i: Integer, cell_j: Integer, origin: Geometry) -> Geometry;
+}
+
+define_sql_function! {
+    /// Returns the square with edge `size` at column `cell_i` and row `cell_j` of the grid starting at `POINT(0 0)`.
+    #[sql_name = "ST_Square"]
+    fn st_square(size: Double, cell_i: Integer, cell_j: Integer) -> Geometry;
+}
+
+define_sql_function! {
+    /// Returns the square with edge `size` at column `cell_i` and row `cell_j` of the grid starting at `origin`.
+    #[sql_name = "ST_Square"]
+    fn st_square_with_origin(size: Double, cell_i: Integer, cell_j: Integer, origin: Geometry) -> Geometry;
+}
+
+define_sql_function! {
+    /// Returns true if the DE-9IM `intersection_matrix` matches `intersection_matrix_pattern`.
+    #[sql_name = "ST_RelateMatch"]
+    fn st_relate_match(intersection_matrix: Text, intersection_matrix_pattern: Text) -> Bool;
+}
+
+define_sql_function! {
+    /// Returns true if the geographies are within `distance_meters` of each other, computed on the spheroid.
+    #[sql_name = "ST_DWithin"]
+    fn st_d_within_geography<G1: GeographyOrNullable + CombinedNullableValue<G2, Bool>, G2: GeographyOrNullable>(gg1: G1, gg2: G2, distance_meters: Double) -> G1::Out;
+}
+
+/// `ST_Transform` call whose target SRID is part of the type, see [`transform`].
+#[derive(Debug, Clone, Copy, ValidGrouping)]
+pub struct Transform<const TO: u32, E> {
+    expr: E,
+}
+
+impl<const TO: u32, E: QueryId> QueryId for Transform<TO, E> {
+    type QueryId = Transform<TO, E::QueryId>;
+
+    const HAS_STATIC_QUERY_ID: bool = E::HAS_STATIC_QUERY_ID;
+}
+
+impl<const TO: u32, E> Expression for Transform<TO, E>
+where
+    E: Expression,
+    E::SqlType: GeometryOrNullable,
+{
+    type SqlType = E::SqlType;
+}
+
+impl<const TO: u32, E> QueryFragment<Pg> for Transform<TO, E>
+where
+    E: QueryFragment<Pg>,
+{
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        out.push_sql("ST_Transform(");
+        self.expr.walk_ast(out.reborrow())?;
+        out.push_sql(", ");
+        out.push_sql(&TO.to_string());
+        out.push_sql(")");
+        Ok(())
+    }
+}
+
+impl<const TO: u32, E, QS> AppearsOnTable<QS> for Transform<TO, E>
+where
+    E: AppearsOnTable<QS>,
+    Self: Expression,
+{
+}
+
+impl<const TO: u32, E, QS> SelectableExpression<QS> for Transform<TO, E>
+where
+    E: SelectableExpression<QS>,
+    Self: AppearsOnTable<QS>,
+{
+}
+
+/// Renders `ST_Transform(expr, TO)`, reprojecting the geometry into the SRID given as const parameter.
+/// Rows selected that way load into the geometry types with the same `SRID`, for example `Point<TO>`,
+/// and loading them into any other SRID fails with [`PostgisError::SridMismatch`](crate::error::PostgisError::SridMismatch).
+/// ```
+/// #[macro_use] extern crate diesel;
+/// use diesel::{debug_query, pg::Pg, prelude::*};
+/// use postgis_diesel::{functions::transform, types::Point};
+/// table! {
+///     use postgis_diesel::sql_types::*;
+///     use diesel::sql_types::*;
+///     places (id) {
+///         id -> Int4,
+///         location -> Geometry,
+///     }
+/// }
+/// let query = places::table.select(transform::<3857, _>(places::location));
+/// assert_eq!(
+///     debug_query::<Pg, _>(&query).to_string(),
+///     "SELECT ST_Transform(\"places\".\"location\", 3857) FROM \"places\" -- binds: []"
+/// );
+/// // loads into `Point<3857>`, `Point<4326>` would fail with `PostgisError::SridMismatch`
+/// let load = |conn: &mut PgConnection| query.load::<Point<3857>>(conn);
+/// ```
+pub fn transform<const TO: u32, E>(expr: E) -> Transform<TO, E>
+where
+    E: Expression,
+    E::SqlType: GeometryOrNullable,
+{
+    Transform { expr }
+}
@@ -1,18 +1,25 @@
-use std::{fmt::Debug, io::Cursor};
+use std::{
+    fmt::Debug,
+    io::{Cursor, Write},
+};
 
 use crate::{
-    error::check_srid,
-    ewkb::{EwkbSerializable, GeometryType, BIG_ENDIAN, self},
-    geometrycollection::{read_geometry_collection_body, write_geometry_collection},
-    linestring::{read_linestring_body, write_linestring},
-    multiline::{read_multiline_body, write_multiline},
-    multipoint::{read_multi_point_body, write_multi_point},
-    multipolygon::{read_multi_polygon_body, write_multi_polygon},
-    points::{read_point_coordinates, write_point},
+    error::PostgisError,
+    ewkb::{
+        self, check_depth, read_all, read_geometry_type, read_nested_header, read_u32, read_u8,
+        write_ewkb_header, EwkbHeader, EwkbSerializable, GeometryType, ReadEwkb, ReadOptions,
+        WriteEwkb, BIG_ENDIAN,
+    },
+    geometrycollection::{read_geometry_collection_body, write_geometry_collection_body},
+    linestring::{read_linestring_body, write_linestring_body},
+    multiline::{read_multiline_body, write_multiline_body},
+    multipoint::{read_multi_point_body, write_multi_point_body},
+    multipolygon::{read_multi_polygon_body, write_multi_polygon_body},
+    points::{read_point_coordinates, write_point_coordinates},
     polygon::*,
     types::*,
 };
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+use byteorder::{BigEndian, LittleEndian};
 use diesel::{
     deserialize::{self, FromSql},
     pg::{self, Pg},
@@ -36,6 +43,47 @@ where
             GeometryContainer::GeometryCollection(g) => g.dimension(),
         }
     }
+
+    pub(crate) fn first_point(&self) -> Option<&T> {
+        match self {
+            GeometryContainer::Point(g) => Some(g).filter(|point| !point.is_empty()),
+            GeometryContainer::LineString(g) => g.first_point(),
+            GeometryContainer::Polygon(g) => g.first_point(),
+            GeometryContainer::MultiPoint(g) => g.first_point(),
+            GeometryContainer::MultiLineString(g) => g.first_point(),
+            GeometryContainer::MultiPolygon(g) => g.first_point(),
+            GeometryContainer::GeometryCollection(g) => g.first_point(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            GeometryContainer::Point(g) => g.is_empty(),
+            GeometryContainer::LineString(g) => g.is_empty(),
+            GeometryContainer::Polygon(g) => g.is_empty(),
+            GeometryContainer::MultiPoint(g) => g.is_empty(),
+            GeometryContainer::MultiLineString(g) => g.is_empty(),
+            GeometryContainer::MultiPolygon(g) => g.is_empty(),
+            GeometryContainer::GeometryCollection(g) => g.is_empty(),
+        }
+    }
+}
+
+impl<const SRID: u32, T> EwkbSerializable for GeometryContainer<SRID, T>
+where
+    T: PointT<SRID> + EwkbSerializable + Clone,
+{
+    fn geometry_type(&self) -> u32 {
+        match self {
+            GeometryContainer::Point(g) => g.geometry_type(),
+            GeometryContainer::LineString(g) => g.geometry_type(),
+            GeometryContainer::Polygon(g) => g.geometry_type(),
+            GeometryContainer::MultiPoint(g) => g.geometry_type(),
+            GeometryContainer::MultiLineString(g) => g.geometry_type(),
+            GeometryContainer::MultiPolygon(g) => g.geometry_type(),
+            GeometryContainer::GeometryCollection(g) => g.geometry_type(),
+        }
+    }
 }
 
 impl<const SRID: u32, T> ToSql<Geometry, Pg> for GeometryContainer<SRID, T>
@@ -43,7 +91,7 @@ where
     T: PointT<SRID> + Debug + PartialEq + Clone + EwkbSerializable,
 {
     fn to_sql(&self, out: &mut Output<Pg>) -> serialize::Result {
-        write_geometry_container(self, out)
+        write_geometry_container::<SRID, LittleEndian, _, _>(self, out)
     }
 }
 
@@ -52,68 +100,193 @@ where
     T: PointT<SRID> + Debug + Clone,
 {
     fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
-        let mut r = Cursor::new(bytes.as_bytes());
-        let end = r.read_u8()?;
-        if end == BIG_ENDIAN {
-            read_geometry_container::<SRID, BigEndian, T>(&mut r)
-        } else {
-            read_geometry_container::<SRID, LittleEndian, T>(&mut r)
-        }
+        Ok(Self::read_ewkb(bytes.as_bytes(), SridPolicy::Strict)?)
+    }
+}
+
+impl<const SRID: u32, T> ReadEwkb for GeometryContainer<SRID, T>
+where
+    T: PointT<SRID> + Clone,
+{
+    fn read_ewkb_with(bytes: &[u8], options: ReadOptions) -> Result<Self, PostgisError> {
+        read_all(bytes, |r| {
+            if read_u8(r)? == BIG_ENDIAN {
+                read_geometry_container::<SRID, BigEndian, T>(r, options)
+            } else {
+                read_geometry_container::<SRID, LittleEndian, T>(r, options)
+            }
+        })
+    }
+}
+
+impl<const SRID: u32, T> WriteEwkb for GeometryContainer<SRID, T>
+where
+    T: PointT<SRID> + EwkbSerializable + Clone,
+{
+    fn write_ewkb<B, W>(&self, srid: Option<u32>, out: &mut W) -> serialize::Result
+    where
+        B: byteorder::ByteOrder,
+        W: Write,
+    {
+        write_ewkb_header::<B, _, _>(self, srid, out)?;
+        write_geometry_container_body::<SRID, B, _, _>(self, self.dimension(), out)
     }
 }
 
 pub fn read_geometry_container<const SRID: u32, T, P>(
     cursor: &mut Cursor<&[u8]>,
-) -> deserialize::Result<GeometryContainer<SRID, P>>
+    options: ReadOptions,
+) -> Result<GeometryContainer<SRID, P>, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID> + Clone,
 {
-    let g_type = cursor.read_u32::<T>()?;
+    let offset = cursor.position();
+    let g_type = read_u32::<T>(cursor)?;
+    let geometry_type = read_geometry_type(g_type, offset)?;
+    let mut srid = None;
     if g_type & ewkb::SRID == ewkb::SRID {
-        let srid = cursor.read_u32::<T>()?;
-        check_srid(Some(srid), SRID)?;
+        srid = Some(read_u32::<T>(cursor)?);
     }
-    Ok(match GeometryType::from(g_type) {
-        GeometryType::Point => {
-            GeometryContainer::Point(read_point_coordinates::<SRID, T, P>(cursor, g_type)?)
-        }
+    let header = EwkbHeader {
+        g_type,
+        srid,
+        offset,
+    };
+    header.check_srid(SRID, options.policy)?;
+    read_geometry_container_body::<SRID, T, P>(g_type, geometry_type, offset, cursor, options)
+}
+
+/// Reads a geometry nested in a collection of type `parent_type`, honouring its own byte order.
+pub fn read_nested_geometry_container<const SRID: u32, P>(
+    parent_type: u32,
+    cursor: &mut Cursor<&[u8]>,
+    options: ReadOptions,
+) -> Result<GeometryContainer<SRID, P>, PostgisError>
+where
+    P: PointT<SRID> + Clone,
+{
+    if read_u8(cursor)? == BIG_ENDIAN {
+        read_nested_geometry_container_body::<SRID, BigEndian, P>(parent_type, cursor, options)
+    } else {
+        read_nested_geometry_container_body::<SRID, LittleEndian, P>(parent_type, cursor, options)
+    }
+}
+
+fn read_nested_geometry_container_body<const SRID: u32, T, P>(
+    parent_type: u32,
+    cursor: &mut Cursor<&[u8]>,
+    options: ReadOptions,
+) -> Result<GeometryContainer<SRID, P>, PostgisError>
+where
+    T: byteorder::ByteOrder,
+    P: PointT<SRID> + Clone,
+{
+    let g_header = read_nested_header::<T>(None, parent_type, cursor)?;
+    let geometry_type = read_geometry_type(g_header.g_type, g_header.offset)?;
+    read_geometry_container_body::<SRID, T, P>(
+        g_header.g_type,
+        geometry_type,
+        g_header.offset,
+        cursor,
+        options,
+    )
+}
+
+/// Reads the geometry whose type code `g_type` is at `offset`, `options.max_depth` being the
+/// number of levels of collections it may still nest.
+fn read_geometry_container_body<const SRID: u32, T, P>(
+    g_type: u32,
+    geometry_type: GeometryType,
+    offset: u64,
+    cursor: &mut Cursor<&[u8]>,
+    options: ReadOptions,
+) -> Result<GeometryContainer<SRID, P>, PostgisError>
+where
+    T: byteorder::ByteOrder,
+    P: PointT<SRID> + Clone,
+{
+    Ok(match geometry_type {
+        GeometryType::Point => GeometryContainer::Point(read_point_coordinates::<SRID, T, P>(
+            cursor, g_type, options,
+        )?),
         GeometryType::LineString => {
-            GeometryContainer::LineString(read_linestring_body::<SRID, T, P>(g_type, cursor)?)
+            GeometryContainer::LineString(read_linestring_body::<SRID, T, P>(
+                g_type, cursor, options,
+            )?)
         }
         GeometryType::Polygon => {
-            GeometryContainer::Polygon(read_polygon_body::<SRID, T, P>(g_type, cursor)?)
+            GeometryContainer::Polygon(read_polygon_body::<SRID, T, P>(g_type, cursor, options)?)
         }
         GeometryType::MultiPoint => {
-            GeometryContainer::MultiPoint(read_multi_point_body::<SRID, T, P>(g_type, cursor)?)
+            GeometryContainer::MultiPoint(read_multi_point_body::<SRID, T, P>(
+                g_type, cursor, options,
+            )?)
         }
         GeometryType::MultiLineString => {
-            GeometryContainer::MultiLineString(read_multiline_body::<SRID, T, P>(g_type, cursor)?)
+            GeometryContainer::MultiLineString(read_multiline_body::<SRID, T, P>(
+                g_type, cursor, options,
+            )?)
         }
         GeometryType::MultiPolygon => {
-            GeometryContainer::MultiPolygon(read_multi_polygon_body::<SRID, T, P>(g_type, cursor)?)
+            GeometryContainer::MultiPolygon(read_multi_polygon_body::<SRID, T, P>(
+                g_type, cursor, options,
+            )?)
+        }
+        GeometryType::GeometryCollection => {
+            let options = options.with_max_depth(check_depth(options.max_depth, offset)?);
+            GeometryContainer::GeometryCollection(read_geometry_collection_body::<SRID, T, P>(
+                g_type, cursor, options,
+            )?)
         }
-        GeometryType::GeometryCollection => GeometryContainer::GeometryCollection(
-            read_geometry_collection_body::<SRID, T, P>(cursor)?,
-        ),
     })
 }
 
-pub fn write_geometry_container<const SRID: u32, T>(
+pub fn write_geometry_container<const SRID: u32, B, T, W>(
+    geometry_container: &GeometryContainer<SRID, T>,
+    out: &mut W,
+) -> serialize::Result
+where
+    B: byteorder::ByteOrder,
+    T: PointT<SRID> + EwkbSerializable + Clone,
+    W: Write,
+{
+    write_ewkb_header::<B, _, _>(geometry_container, Some(SRID), out)?;
+    write_geometry_container_body::<SRID, B, _, _>(
+        geometry_container,
+        geometry_container.dimension(),
+        out,
+    )
+}
+
+pub fn write_geometry_container_body<const SRID: u32, B, T, W>(
     geometry_container: &GeometryContainer<SRID, T>,
-    out: &mut Output<Pg>,
+    dimension: u32,
+    out: &mut W,
 ) -> serialize::Result
 where
+    B: byteorder::ByteOrder,
     T: PointT<SRID> + EwkbSerializable + Clone,
+    W: Write,
 {
     match geometry_container {
-        GeometryContainer::Point(g) => write_point(g, out)?,
-        GeometryContainer::LineString(g) => write_linestring(g, out)?,
-        GeometryContainer::Polygon(g) => write_polygon(g, out)?,
-        GeometryContainer::MultiPoint(g) => write_multi_point(g, out)?,
-        GeometryContainer::MultiLineString(g) => write_multiline(g, out)?,
-        GeometryContainer::MultiPolygon(g) => write_multi_polygon(g, out)?,
-        GeometryContainer::GeometryCollection(g) => write_geometry_collection(g, out)?,
+        GeometryContainer::Point(g) => write_point_coordinates::<SRID, B, _, _>(g, dimension, out)?,
+        GeometryContainer::LineString(g) => {
+            write_linestring_body::<SRID, B, _, _>(g, dimension, out)?
+        }
+        GeometryContainer::Polygon(g) => write_polygon_body::<SRID, B, _, _>(g, dimension, out)?,
+        GeometryContainer::MultiPoint(g) => {
+            write_multi_point_body::<SRID, B, _, _>(g, dimension, out)?
+        }
+        GeometryContainer::MultiLineString(g) => {
+            write_multiline_body::<SRID, B, _, _>(g, dimension, out)?
+        }
+        GeometryContainer::MultiPolygon(g) => {
+            write_multi_polygon_body::<SRID, B, _, _>(g, dimension, out)?
+        }
+        GeometryContainer::GeometryCollection(g) => {
+            write_geometry_collection_body::<SRID, B, _, _>(g, dimension, out)?
+        }
     };
     Ok(IsNull::No)
 }
@@ -0,0 +1,24 @@
+use std::fmt::Debug;
+
+use diesel::{
+    deserialize::{self, FromSql},
+    pg::{self, Pg},
+};
+
+use crate::{
+    sql_types::*,
+    types::{GeometryContainer, GeometryDump, PointT},
+};
+
+impl<const SRID: u32, T> FromSql<GeometryDumpRecord, Pg> for GeometryDump<SRID, T>
+where
+    T: PointT<SRID> + Debug + Clone,
+{
+    fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
+        let (path, geom) = <(Vec<i32>, GeometryContainer<SRID, T>) as FromSql<
+            GeometryDumpRecord,
+            Pg,
+        >>::from_sql(bytes)?;
+        Ok(GeometryDump { path, geom })
+    }
+}
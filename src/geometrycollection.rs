@@ -1,14 +1,21 @@
-use std::{fmt::Debug, io::Cursor};
+use std::{
+    fmt::Debug,
+    io::{Cursor, Write},
+};
 
 use crate::{
-    error::check_srid,
-    ewkb::{read_ewkb_header, write_ewkb_header, EwkbSerializable, GeometryType, BIG_ENDIAN},
-    geometry_container::{read_geometry_container, write_geometry_container},
-    points::Dimension,
+    error::PostgisError,
+    ewkb::{
+        check_depth, read_all, read_count, read_ewkb_header, read_u8, write_ewkb_header,
+        write_nested_header, EwkbSerializable, GeometryType, ReadEwkb, ReadOptions, WriteEwkb,
+        BIG_ENDIAN, NESTED_GEOMETRY_MIN_SIZE,
+    },
+    geometry_container::{read_nested_geometry_container, write_geometry_container_body},
+    points::points_dimension,
     sql_types::*,
     types::*,
 };
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
 use diesel::{
     deserialize::{self, FromSql},
     pg::{self, Pg},
@@ -25,12 +32,19 @@ where
         }
     }
 
+    /// Dimension of the first non-empty point, the one of the point type `T` when there is none.
     pub fn dimension(&self) -> u32 {
-        let mut dimension = Dimension::None as u32;
-        if let Some(geometry) = self.geometries.first() {
-            dimension |= geometry.dimension();
-        }
-        dimension
+        points_dimension(self.first_point())
+    }
+
+    pub(crate) fn first_point(&self) -> Option<&T> {
+        self.geometries
+            .iter()
+            .find_map(GeometryContainer::first_point)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.geometries.iter().all(|geometry| geometry.is_empty())
     }
 }
 
@@ -39,11 +53,7 @@ where
     T: PointT<SRID> + Clone,
 {
     fn geometry_type(&self) -> u32 {
-        let mut g_type = GeometryType::GeometryCollection as u32;
-        if let Some(polygon) = self.geometries.first() {
-            g_type |= polygon.dimension();
-        }
-        g_type
+        GeometryType::GeometryCollection as u32 | self.dimension()
     }
 }
 
@@ -52,7 +62,7 @@ where
     T: PointT<SRID> + Debug + PartialEq + Clone + EwkbSerializable,
 {
     fn to_sql(&self, out: &mut Output<Pg>) -> serialize::Result {
-        write_geometry_collection(self, out)
+        write_geometry_collection::<SRID, LittleEndian, _, _>(self, out)
     }
 }
 
@@ -61,56 +71,105 @@ where
     T: PointT<SRID> + Debug + Clone,
 {
     fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
-        let mut r = Cursor::new(bytes.as_bytes());
-        let end = r.read_u8()?;
-        if end == BIG_ENDIAN {
-            read_geometry_collection::<SRID, BigEndian, T>(&mut r)
-        } else {
-            read_geometry_collection::<SRID, LittleEndian, T>(&mut r)
-        }
+        Ok(Self::read_ewkb(bytes.as_bytes(), SridPolicy::Strict)?)
+    }
+}
+
+impl<const SRID: u32, T> ReadEwkb for GeometryCollection<SRID, T>
+where
+    T: PointT<SRID> + Clone,
+{
+    fn read_ewkb_with(bytes: &[u8], options: ReadOptions) -> Result<Self, PostgisError> {
+        read_all(bytes, |r| {
+            if read_u8(r)? == BIG_ENDIAN {
+                read_geometry_collection::<SRID, BigEndian, T>(r, options)
+            } else {
+                read_geometry_collection::<SRID, LittleEndian, T>(r, options)
+            }
+        })
     }
 }
 
-pub fn write_geometry_collection<const SRID: u32, T>(
+impl<const SRID: u32, T> WriteEwkb for GeometryCollection<SRID, T>
+where
+    T: PointT<SRID> + EwkbSerializable + Clone,
+{
+    fn write_ewkb<B, W>(&self, srid: Option<u32>, out: &mut W) -> serialize::Result
+    where
+        B: byteorder::ByteOrder,
+        W: Write,
+    {
+        write_ewkb_header::<B, _, _>(self, srid, out)?;
+        write_geometry_collection_body::<SRID, B, _, _>(self, self.dimension(), out)
+    }
+}
+
+pub fn write_geometry_collection<const SRID: u32, B, T, W>(
+    geometrycollection: &GeometryCollection<SRID, T>,
+    out: &mut W,
+) -> serialize::Result
+where
+    B: byteorder::ByteOrder,
+    T: PointT<SRID> + EwkbSerializable + Clone,
+    W: Write,
+{
+    write_ewkb_header::<B, _, _>(geometrycollection, Some(SRID), out)?;
+    write_geometry_collection_body::<SRID, B, _, _>(
+        geometrycollection,
+        geometrycollection.dimension(),
+        out,
+    )
+}
+
+pub fn write_geometry_collection_body<const SRID: u32, B, T, W>(
     geometrycollection: &GeometryCollection<SRID, T>,
-    out: &mut Output<Pg>,
+    dimension: u32,
+    out: &mut W,
 ) -> serialize::Result
 where
+    B: byteorder::ByteOrder,
     T: PointT<SRID> + EwkbSerializable + Clone,
+    W: Write,
 {
-    write_ewkb_header(geometrycollection, Some(SRID), out)?;
-    out.write_u32::<LittleEndian>(geometrycollection.geometries.len() as u32)?;
+    // number of geometries, each with its own header but without SRID
+    out.write_u32::<B>(geometrycollection.geometries.len() as u32)?;
     for g_container in geometrycollection.geometries.iter() {
-        write_geometry_container(g_container, out)?;
+        write_nested_header::<B, _, _>(g_container, dimension, out)?;
+        write_geometry_container_body::<SRID, B, _, _>(g_container, dimension, out)?;
     }
     Ok(IsNull::No)
 }
 
 fn read_geometry_collection<const SRID: u32, T, P>(
     cursor: &mut Cursor<&[u8]>,
-) -> deserialize::Result<GeometryCollection<SRID, P>>
+    options: ReadOptions,
+) -> Result<GeometryCollection<SRID, P>, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID> + Clone,
 {
     let g_header = read_ewkb_header::<T>(GeometryType::GeometryCollection, cursor)?;
-    check_srid(g_header.srid, SRID)?;
-    read_geometry_collection_body::<SRID, T, P>(cursor)
+    g_header.check_srid(SRID, options.policy)?;
+    let options = options.with_max_depth(check_depth(options.max_depth, g_header.offset)?);
+    read_geometry_collection_body::<SRID, T, P>(g_header.g_type, cursor, options)
 }
 
+/// Reads the geometries of a collection, `options.max_depth` being the number of levels of
+/// collections they may still nest.
 pub fn read_geometry_collection_body<const SRID: u32, T, P>(
+    g_type: u32,
     cursor: &mut Cursor<&[u8]>,
-) -> deserialize::Result<GeometryCollection<SRID, P>>
+    options: ReadOptions,
+) -> Result<GeometryCollection<SRID, P>, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID> + Clone,
 {
-    let geometries_n = cursor.read_u32::<T>()?;
+    let geometries_n = read_count::<T>(cursor, NESTED_GEOMETRY_MIN_SIZE)?;
     let mut g_collection = GeometryCollection::new();
-    for _i in 0..geometries_n {
-        // skip 1 byte for byte order and 4 bytes for point type
-        cursor.read_u8()?;
-        let g_container = read_geometry_container::<SRID, T, P>(cursor)?;
+    for i in 0..geometries_n {
+        let g_container = read_nested_geometry_container::<SRID, P>(g_type, cursor, options)
+            .map_err(|e| e.nested(i))?;
         g_collection.geometries.push(g_container);
     }
     Ok(g_collection)
@@ -1,4 +1,12 @@
-use crate::types;
+use diesel::{dsl, sql_types::Nullable, BoolExpressionMethods, Expression, IntoSql};
+
+use crate::{
+    expression_methods::GeometryExpressionMethods,
+    functions::{st_d_within_geography, st_distance_geography, st_make_envelope},
+    operators::{intersects_2d, BBIntersects2D},
+    sql_types::{Geography, Geometry, GeometryOrNullable},
+    types,
+};
 
 pub const SRID: u32 = 4326;
 
@@ -27,3 +35,164 @@ pub type PolygonZ = types::Polygon<SRID, PointZ>;
 pub type MultiPolygonZ = types::MultiPolygon<SRID, PointZ>;
 pub type GeometryContainerZ = types::GeometryContainer<SRID, PointZ>;
 pub type GeometryCollectionZ = types::GeometryCollection<SRID, PointZ>;
+
+/// Meters in a degree of latitude at the equator, the shortest degree on the spheroid.
+const MIN_METERS_PER_DEGREE: f64 = 110_574.0;
+
+type GeographyPoint = dsl::Cast<dsl::AsExprOf<Point, Geometry>, Geography>;
+type Envelope = st_make_envelope<f64, f64, f64, f64, i32>;
+
+/// The return type of [`within_meters`].
+pub type WithinMeters<C> = <<C as Expression>::SqlType as GpsGeometryOrNullable>::WithinMeters<C>;
+
+/// The return type of [`distance_meters`].
+pub type DistanceMeters<C> =
+    <<C as Expression>::SqlType as GpsGeometryOrNullable>::DistanceMeters<C>;
+
+/// [`Geometry`] or `Nullable<Geometry>`, the SQL type of the columns accepted by
+/// [`within_meters`] and [`distance_meters`]. Their result is nullable if the column is.
+pub trait GpsGeometryOrNullable: GeometryOrNullable {
+    type WithinMeters<C: Expression<SqlType = Self> + Copy>;
+    type DistanceMeters<C: Expression<SqlType = Self>>;
+
+    fn within_meters<C>(column: C, point: Point, meters: f64) -> Self::WithinMeters<C>
+    where
+        C: Expression<SqlType = Self> + Copy;
+
+    fn distance_meters<C>(column: C, point: Point) -> Self::DistanceMeters<C>
+    where
+        C: Expression<SqlType = Self>;
+}
+
+macro_rules! impl_gps_geometry {
+    ($geometry:ty, $geography:ty) => {
+        impl GpsGeometryOrNullable for $geometry {
+            type WithinMeters<C: Expression<SqlType = Self> + Copy> = dsl::And<
+                BBIntersects2D<C, Envelope>,
+                st_d_within_geography<
+                    $geography,
+                    Geography,
+                    dsl::Cast<C, $geography>,
+                    GeographyPoint,
+                    f64,
+                >,
+            >;
+            type DistanceMeters<C: Expression<SqlType = Self>> = st_distance_geography<
+                $geography,
+                Geography,
+                dsl::Cast<C, $geography>,
+                GeographyPoint,
+                bool,
+            >;
+
+            fn within_meters<C>(column: C, point: Point, meters: f64) -> Self::WithinMeters<C>
+            where
+                C: Expression<SqlType = Self> + Copy,
+            {
+                let (xmin, ymin, xmax, ymax) = envelope_around(point, meters);
+                intersects_2d(
+                    column,
+                    st_make_envelope(xmin, ymin, xmax, ymax, SRID as i32),
+                )
+                .and(st_d_within_geography(
+                    column.to_geography(),
+                    point.into_sql::<Geometry>().to_geography(),
+                    meters,
+                ))
+            }
+
+            fn distance_meters<C>(column: C, point: Point) -> Self::DistanceMeters<C>
+            where
+                C: Expression<SqlType = Self>,
+            {
+                st_distance_geography(
+                    column.to_geography(),
+                    point.into_sql::<Geometry>().to_geography(),
+                    true,
+                )
+            }
+        }
+    };
+}
+
+impl_gps_geometry!(Geometry, Geography);
+impl_gps_geometry!(Nullable<Geometry>, Nullable<Geography>);
+
+/// Filters rows whose geometry `column` (in SRID 4326) is within `meters` of `point`.
+///
+/// Renders `column && ST_MakeEnvelope(..) AND ST_DWithin(column::geography, point::geography,
+/// meters)`: the envelope around `point` covers at least `meters` in every direction, so that a
+/// GiST index on the geometry column prefilters rows before the distance is computed on the
+/// spheroid. The filter is NULL for rows whose `column` is NULL.
+/// ```
+/// #[macro_use] extern crate diesel;
+/// use diesel::{debug_query, pg::Pg, prelude::*};
+/// use postgis_diesel::gps::{distance_meters, within_meters, Point};
+///
+/// table! {
+///     use postgis_diesel::sql_types::*;
+///     use diesel::sql_types::*;
+///     places (id) {
+///         id -> Int4,
+///         location -> Geometry,
+///         entrance -> Nullable<Geometry>,
+///     }
+/// }
+///
+/// let query = places::table
+///     .filter(within_meters(places::location, Point::new(13.4, 52.5), 500.0))
+///     .select((places::id, distance_meters(places::location, Point::new(13.4, 52.5))));
+/// assert!(debug_query::<Pg, _>(&query).to_string().starts_with(
+///     "SELECT \"places\".\"id\", ST_Distance(CAST(\"places\".\"location\" AS geography), \
+///      CAST($1 AS geography), $2) FROM \"places\" WHERE (\"places\".\"location\" && \
+///      ST_MakeEnvelope($3, $4, $5, $6, $7) AND ST_DWithin(CAST(\"places\".\"location\" AS geography), \
+///      CAST($8 AS geography), $9))"
+/// ));
+///
+/// // the distance is None for places without entrance
+/// let query = places::table
+///     .filter(within_meters(places::entrance, Point::new(13.4, 52.5), 500.0))
+///     .select(distance_meters(places::entrance, Point::new(13.4, 52.5)));
+/// let _load = |conn: &mut PgConnection| -> QueryResult<Vec<Option<f64>>> { query.load(conn) };
+/// ```
+pub fn within_meters<C>(column: C, point: Point, meters: f64) -> WithinMeters<C>
+where
+    C: Expression + Copy,
+    C::SqlType: GpsGeometryOrNullable,
+{
+    C::SqlType::within_meters(column, point, meters)
+}
+
+/// Distance in meters between geometry `column` (in SRID 4326) and `point`, computed on the
+/// spheroid. It is NULL if `column` is.
+pub fn distance_meters<C>(column: C, point: Point) -> DistanceMeters<C>
+where
+    C: Expression,
+    C::SqlType: GpsGeometryOrNullable,
+{
+    C::SqlType::distance_meters(column, point)
+}
+
+/// Box in degrees covering at least `meters` around `point`, spanning all longitudes near the
+/// poles and the antimeridian.
+fn envelope_around(point: Point, meters: f64) -> (f64, f64, f64, f64) {
+    // 1% margin against the rounding of the spheroid by the constant above
+    let dy = meters * 1.01 / MIN_METERS_PER_DEGREE;
+    let max_lat = point.y.abs() + dy;
+    let dx = if max_lat >= 90.0 {
+        180.0
+    } else {
+        dy / max_lat.to_radians().cos()
+    };
+    let (xmin, xmax) = if point.x - dx < -180.0 || point.x + dx > 180.0 {
+        (-180.0, 180.0)
+    } else {
+        (point.x - dx, point.x + dx)
+    };
+    (
+        xmin,
+        (point.y - dy).max(-90.0),
+        xmax,
+        (point.y + dy).min(90.0),
+    )
+}
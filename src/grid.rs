@@ -0,0 +1,187 @@
+//! Rust counterparts of the PostGIS grid generators `ST_Hexagon`, `ST_Square`, `ST_HexagonGrid`
+//! and `ST_SquareGrid`.
+//!
+//! Cells are addressed the same way as in PostGIS: the grid starts at the origin, `i` counts
+//! columns along X and `j` counts rows along Y, so a cell computed here can be compared with the
+//! cell of the same index returned by the database.
+//! ```
+//! use postgis_diesel::{grid, types::Point};
+//! let cells = grid::square_grid(1.0, Point::<4326>::new(0.5, 0.5), Point::new(1.5, 0.5));
+//! assert_eq!(
+//!     cells.iter().map(|c| (c.i, c.j)).collect::<Vec<_>>(),
+//!     vec![(0, 0), (1, 0)]
+//! );
+//! ```
+use std::fmt::Debug;
+
+use diesel::{
+    deserialize::{self, FromSql},
+    pg::{self, Pg},
+};
+
+use crate::{
+    sql_types::*,
+    types::{GridCell, LineString, Point, PointT, Polygon},
+};
+
+/// Half of the hexagon height relative to its edge size, `sqrt(3) / 2`.
+const H: f64 = 0.866_025_403_784_438_6;
+
+const HEX_X: [f64; 7] = [-1.0, -0.5, 0.5, 1.0, 0.5, -0.5, -1.0];
+const HEX_Y: [f64; 7] = [0.0, -0.5, -0.5, 0.0, 0.5, 0.5, 0.0];
+
+impl<const SRID: u32, T> FromSql<GridCellRecord, Pg> for GridCell<SRID, T>
+where
+    T: PointT<SRID> + Debug + Clone,
+{
+    fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
+        let (geom, i, j) =
+            <(Polygon<SRID, T>, i32, i32) as FromSql<GridCellRecord, Pg>>::from_sql(bytes)?;
+        Ok(GridCell { geom, i, j })
+    }
+}
+
+/// Hexagon with edge `size` at column `cell_i` and row `cell_j` of the grid starting at `origin`,
+/// same as `ST_Hexagon`.
+pub fn hexagon<const SRID: u32>(
+    size: f64,
+    cell_i: i32,
+    cell_j: i32,
+    origin: Point<SRID>,
+) -> Polygon<SRID, Point<SRID>> {
+    let height = size * 2.0 * H;
+    let offset_x = origin.x + 1.5 * size * cell_i as f64;
+    let offset_y = origin.y + height * cell_j as f64 + 0.5 * height * (cell_i.abs() % 2) as f64;
+    let points = HEX_X
+        .iter()
+        .zip(HEX_Y.iter())
+        .map(|(x, y)| Point::new(size * x + offset_x, height * y + offset_y))
+        .collect();
+    Polygon {
+        rings: vec![LineString { points }],
+    }
+}
+
+/// Square with edge `size` at column `cell_i` and row `cell_j` of the grid starting at `origin`,
+/// same as `ST_Square`.
+pub fn square<const SRID: u32>(
+    size: f64,
+    cell_i: i32,
+    cell_j: i32,
+    origin: Point<SRID>,
+) -> Polygon<SRID, Point<SRID>> {
+    let ll_x = origin.x + size * cell_i as f64;
+    let ll_y = origin.y + size * cell_j as f64;
+    let ur_x = origin.x + size * (cell_i + 1) as f64;
+    let ur_y = origin.y + size * (cell_j + 1) as f64;
+    let points = vec![
+        Point::new(ll_x, ll_y),
+        Point::new(ll_x, ur_y),
+        Point::new(ur_x, ur_y),
+        Point::new(ur_x, ll_y),
+        Point::new(ll_x, ll_y),
+    ];
+    Polygon {
+        rings: vec![LineString { points }],
+    }
+}
+
+/// Hexagons with edge `size` covering the box from `min` to `max`, in the order `ST_HexagonGrid`
+/// returns them (column by column).
+pub fn hexagon_grid<const SRID: u32>(
+    size: f64,
+    min: Point<SRID>,
+    max: Point<SRID>,
+) -> Vec<GridCell<SRID, Point<SRID>>> {
+    let mut cells = Vec::new();
+    if size <= 0.0 || min.x > max.x || min.y > max.y {
+        return cells;
+    }
+    let col_width = 1.5 * size;
+    let row_height = size * 2.0 * H;
+
+    // a column may only touch the box with its side corners, skip those
+    let mut column_min = (min.x / col_width).floor() as i32;
+    if min.x - column_min as f64 * col_width > size {
+        column_min += 1;
+    }
+    let mut column_max = (max.x / col_width).ceil() as i32;
+    if column_max as f64 * col_width - max.x > size {
+        column_max -= 1;
+    }
+    // odd columns are shifted up by half a row
+    let row_min_even = (min.y / row_height + 0.5).floor() as i32;
+    let row_max_even = (max.y / row_height + 0.5).floor() as i32;
+    let row_min_odd = (min.y / row_height).floor() as i32;
+    let row_max_odd = (max.y / row_height).floor() as i32;
+
+    let origin = Point::new(0.0, 0.0);
+    for i in column_min..=column_max {
+        let (row_min, row_max) = if i % 2 != 0 {
+            (row_min_odd, row_max_odd)
+        } else {
+            (row_min_even, row_max_even)
+        };
+        for j in row_min..=row_max {
+            cells.push(GridCell {
+                geom: hexagon(size, i, j, origin),
+                i,
+                j,
+            });
+        }
+    }
+    cells
+}
+
+/// Squares with edge `size` covering the box from `min` to `max`, in the order `ST_SquareGrid`
+/// returns them (column by column).
+pub fn square_grid<const SRID: u32>(
+    size: f64,
+    min: Point<SRID>,
+    max: Point<SRID>,
+) -> Vec<GridCell<SRID, Point<SRID>>> {
+    let mut cells = Vec::new();
+    if size <= 0.0 || min.x > max.x || min.y > max.y {
+        return cells;
+    }
+    let column_min = (min.x / size).floor() as i32;
+    let column_max = (max.x / size).floor() as i32;
+    let row_min = (min.y / size).floor() as i32;
+    let row_max = (max.y / size).floor() as i32;
+
+    let origin = Point::new(0.0, 0.0);
+    for i in column_min..=column_max {
+        for j in row_min..=row_max {
+            cells.push(GridCell {
+                geom: square(size, i, j, origin),
+                i,
+                j,
+            });
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hexagon_test() {
+        let hex = hexagon(2.0, 1, 0, Point::<0>::new(0.0, 0.0));
+        let ring = &hex.rings[0].points;
+        assert_eq!(ring.len(), 7);
+        assert_eq!(ring.first(), ring.last());
+        // odd columns are shifted up by half a row
+        assert_eq!(ring[0], Point::new(1.0, 2.0 * H));
+        assert_eq!(ring[3], Point::new(5.0, 2.0 * H));
+    }
+
+    #[test]
+    fn hexagon_grid_test() {
+        let cells = hexagon_grid(1.0, Point::<0>::new(0.1, 0.1), Point::new(2.0, 1.0));
+        let indices: Vec<(i32, i32)> = cells.iter().map(|c| (c.i, c.j)).collect();
+        assert_eq!(indices, vec![(0, 0), (0, 1), (1, 0), (2, 0), (2, 1)]);
+        assert!(hexagon_grid(0.0, Point::<0>::new(0.0, 0.0), Point::new(1.0, 1.0)).is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+use std::{fmt, io::Write, str::FromStr};
+
+use diesel::{
+    deserialize::{self, FromSql},
+    pg::{self, Pg},
+    serialize::{self, IsNull, Output, ToSql},
+    sql_types::Text,
+};
+
+use crate::{error::IntersectionMatrixError, types::IntersectionMatrix};
+
+impl IntersectionMatrix {
+    /// Checks the matrix against a DE-9IM pattern such as `"T*F**F***"`, same as `ST_RelateMatch`.
+    /// `T` matches any non-empty intersection, `F` an empty one, `*` anything and `0`, `1`, `2`
+    /// that exact dimension. Patterns which are not 9 characters long never match.
+    pub fn matches(&self, pattern: &str) -> bool {
+        pattern.chars().count() == 9
+            && pattern
+                .chars()
+                .zip(self.cells.iter())
+                .all(|(p, cell)| match p {
+                    '*' => true,
+                    'T' | 't' => cell.is_some(),
+                    'F' | 'f' => cell.is_none(),
+                    '0'..='2' => p.to_digit(10).map(|d| d as u8) == *cell,
+                    _ => false,
+                })
+    }
+
+    pub fn is_equals(&self) -> bool {
+        self.matches("T*F**FFF*")
+    }
+
+    pub fn is_disjoint(&self) -> bool {
+        self.matches("FF*FF****")
+    }
+
+    pub fn is_intersects(&self) -> bool {
+        !self.is_disjoint()
+    }
+
+    pub fn is_touches(&self) -> bool {
+        self.matches("FT*******") || self.matches("F**T*****") || self.matches("F***T****")
+    }
+
+    pub fn is_within(&self) -> bool {
+        self.matches("T*F**F***")
+    }
+
+    pub fn is_contains(&self) -> bool {
+        self.matches("T*****FF*")
+    }
+
+    pub fn is_covers(&self) -> bool {
+        self.matches("T*****FF*")
+            || self.matches("*T****FF*")
+            || self.matches("***T**FF*")
+            || self.matches("****T*FF*")
+    }
+
+    pub fn is_covered_by(&self) -> bool {
+        self.matches("T*F**F***")
+            || self.matches("*TF**F***")
+            || self.matches("**FT*F***")
+            || self.matches("**F*TF***")
+    }
+}
+
+impl FromStr for IntersectionMatrix {
+    type Err = IntersectionMatrixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || IntersectionMatrixError {
+            matrix: s.to_string(),
+        };
+        if s.chars().count() != 9 {
+            return Err(err());
+        }
+        let mut cells = [None; 9];
+        for (cell, c) in cells.iter_mut().zip(s.chars()) {
+            *cell = match c {
+                'F' | 'f' => None,
+                '0'..='2' => c.to_digit(10).map(|d| d as u8),
+                _ => return Err(err()),
+            };
+        }
+        Ok(IntersectionMatrix { cells })
+    }
+}
+
+impl fmt::Display for IntersectionMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for cell in self.cells.iter() {
+            match cell {
+                Some(dimension) => write!(f, "{}", dimension)?,
+                None => write!(f, "F")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromSql<Text, Pg> for IntersectionMatrix {
+    fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
+        let matrix = std::str::from_utf8(bytes.as_bytes())?;
+        Ok(matrix.parse()?)
+    }
+}
+
+impl ToSql<Text, Pg> for IntersectionMatrix {
+    fn to_sql(&self, out: &mut Output<Pg>) -> serialize::Result {
+        out.write_all(self.to_string().as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
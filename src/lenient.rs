@@ -0,0 +1,168 @@
+use std::{fmt::Debug, marker::PhantomData, ops::Deref};
+
+use diesel::{
+    deserialize::{self, FromSql},
+    pg::{self, Pg},
+    serialize::{self, Output, ToSql},
+};
+
+use crate::{
+    error::PostgisError,
+    ewkb::{ReadEwkb, ReadOptions},
+    sql_types::Geometry,
+    types::{AcceptMissing, Lenient, SridPolicy, SridPolicyMarker, ZeroIsUnknown},
+};
+
+impl SridPolicy {
+    /// Whether a geometry with SRID `found` may be read into a type with SRID `expected`.
+    pub fn accepts(self, found: Option<u32>, expected: u32) -> bool {
+        match self {
+            SridPolicy::Strict => found == Some(expected),
+            SridPolicy::AcceptMissing => found.is_none() || found == Some(expected),
+            SridPolicy::ZeroIsUnknown => {
+                expected == 0 || found.is_none() || found == Some(0) || found == Some(expected)
+            }
+            SridPolicy::Any => true,
+        }
+    }
+}
+
+impl SridPolicyMarker for AcceptMissing {
+    const POLICY: SridPolicy = SridPolicy::AcceptMissing;
+}
+
+impl SridPolicyMarker for ZeroIsUnknown {
+    const POLICY: SridPolicy = SridPolicy::ZeroIsUnknown;
+}
+
+impl<G, P> Lenient<G, P> {
+    pub fn new(geometry: G) -> Self {
+        Self {
+            geometry,
+            policy: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> G {
+        self.geometry
+    }
+}
+
+impl<G, P> From<G> for Lenient<G, P> {
+    fn from(geometry: G) -> Self {
+        Self::new(geometry)
+    }
+}
+
+impl<G, P> Deref for Lenient<G, P> {
+    type Target = G;
+
+    fn deref(&self) -> &G {
+        &self.geometry
+    }
+}
+
+impl<G, P> FromSql<Geometry, Pg> for Lenient<G, P>
+where
+    G: ReadEwkb,
+    P: SridPolicyMarker,
+{
+    fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
+        Ok(Self::read_ewkb(bytes.as_bytes(), P::POLICY)?)
+    }
+}
+
+/// Reads `G` with the policy `P`, in place of the one of the options.
+impl<G, P> ReadEwkb for Lenient<G, P>
+where
+    G: ReadEwkb,
+    P: SridPolicyMarker,
+{
+    fn read_ewkb_with(bytes: &[u8], options: ReadOptions) -> Result<Self, PostgisError> {
+        Ok(Self::new(G::read_ewkb_with(
+            bytes,
+            options.with_policy(P::POLICY),
+        )?))
+    }
+}
+
+impl<G, P> ToSql<Geometry, Pg> for Lenient<G, P>
+where
+    G: ToSql<Geometry, Pg>,
+    P: Debug,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        self.geometry.to_sql(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::LittleEndian;
+
+    use super::*;
+    use crate::{
+        ewkb::{
+            tests::{at, bytes, nested_collection, MULTI_POINT},
+            WriteEwkb,
+        },
+        types::*,
+    };
+
+    #[test]
+    fn lenient_test() {
+        let multi_point = MultiPoint::<4326, Point<4326>> {
+            points: vec![Point::new(1.0, 2.0), Point::new(3.0, 4.0)],
+        };
+        // SRID flag cleared and SRID removed
+        let mut missing = bytes(MULTI_POINT);
+        missing[4] = 0;
+        missing.drain(5..9);
+        // SRID present but 0
+        let mut zero = bytes(MULTI_POINT);
+        zero[5..9].copy_from_slice(&[0; 4]);
+
+        // the policy of the wrapper replaces the one it is read with
+        assert_eq!(
+            Ok(multi_point.clone()),
+            Lenient::<MultiPoint<4326, Point<4326>>>::read_ewkb(&missing, SridPolicy::Strict)
+                .map(Lenient::into_inner)
+        );
+        assert!(
+            Lenient::<MultiPoint<4326, Point<4326>>>::read_ewkb(&zero, SridPolicy::Strict).is_err()
+        );
+        assert_eq!(
+            Ok(multi_point),
+            Lenient::<MultiPoint<4326, Point<4326>>, ZeroIsUnknown>::read_ewkb(
+                &zero,
+                SridPolicy::Strict
+            )
+            .map(Lenient::into_inner)
+        );
+        assert_eq!(
+            Err(PostgisError::SridMismatch {
+                expected: 3857,
+                found: Some(4326),
+                location: at(1, vec![]),
+            }),
+            Lenient::<MultiPoint<3857, Point<3857>>>::read_ewkb(
+                &bytes(MULTI_POINT),
+                SridPolicy::Any
+            )
+            .map(Lenient::into_inner)
+        );
+
+        // the wrappers override their own option only
+        let mut missing = Vec::new();
+        nested_collection()
+            .write_ewkb::<LittleEndian, _>(None, &mut missing)
+            .unwrap();
+        assert!(
+            Lenient::<MaxDepth<GeometryCollection<4326, Point<4326>>, 2>>::read_ewkb(
+                &missing,
+                SridPolicy::Strict
+            )
+            .is_err()
+        );
+    }
+}
@@ -1,10 +1,19 @@
 #[macro_use]
 extern crate diesel;
 
+mod any_srid;
 mod ewkb;
+pub mod expression_methods;
+mod force;
+pub mod functions;
 mod geometrycollection;
 mod geometry_container;
+mod geometry_dump;
+pub mod grid;
+mod intersection_matrix;
+mod lenient;
 mod linestring;
+mod max_depth;
 mod multiline;
 mod multipoint;
 mod multipolygon;
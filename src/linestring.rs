@@ -1,17 +1,25 @@
 use crate::{
-    error::check_srid,
-    ewkb::{read_ewkb_header, write_ewkb_header, EwkbSerializable, GeometryType, BIG_ENDIAN},
-    points::{read_point_coordinates, write_point_coordinates, Dimension},
+    error::PostgisError,
+    ewkb::{
+        coordinates_size, read_all, read_count, read_ewkb_header, read_nested_header, read_u8,
+        write_ewkb_header, EwkbSerializable, GeometryType, ReadEwkb, ReadOptions, WriteEwkb,
+        BIG_ENDIAN,
+    },
+    points::{points_dimension, read_point_coordinates, write_point_coordinates},
     sql_types::*,
-    types::{LineString, PointT},
+    types::{LineString, PointT, SridPolicy},
 };
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
 use diesel::{
     deserialize::{self, FromSql},
     pg::{self, Pg},
     serialize::{self, IsNull, Output, ToSql},
 };
-use std::{fmt::Debug, io::Cursor, iter::FromIterator};
+use std::{
+    fmt::Debug,
+    io::{Cursor, Write},
+    iter::FromIterator,
+};
 
 impl<const SRID: u32, P: PointT<SRID>> FromIterator<P> for LineString<SRID, P> {
     fn from_iter<T: IntoIterator<Item = P>>(iter: T) -> Self {
@@ -33,12 +41,17 @@ impl<const SRID: u32, T> LineString<SRID, T>
 where
     T: PointT<SRID>,
 {
+    /// Dimension of the first non-empty point, the one of the point type `T` when there is none.
     pub fn dimension(&self) -> u32 {
-        let mut dimension = Dimension::None as u32;
-        if let Some(point) = self.points.first() {
-            dimension |= point.dimension();
-        }
-        dimension
+        points_dimension(self.first_point())
+    }
+
+    pub(crate) fn first_point(&self) -> Option<&T> {
+        self.points.iter().find(|point| !point.is_empty())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
     }
 }
 
@@ -47,13 +60,36 @@ where
     T: PointT<SRID> + Debug + Clone,
 {
     fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
-        let mut r = Cursor::new(bytes.as_bytes());
-        let end = r.read_u8()?;
-        if end == BIG_ENDIAN {
-            read_linestring::<SRID, BigEndian, T>(&mut r)
-        } else {
-            read_linestring::<SRID, LittleEndian, T>(&mut r)
-        }
+        Ok(Self::read_ewkb(bytes.as_bytes(), SridPolicy::Strict)?)
+    }
+}
+
+impl<const SRID: u32, T> ReadEwkb for LineString<SRID, T>
+where
+    T: PointT<SRID> + Clone,
+{
+    fn read_ewkb_with(bytes: &[u8], options: ReadOptions) -> Result<Self, PostgisError> {
+        read_all(bytes, |r| {
+            if read_u8(r)? == BIG_ENDIAN {
+                read_linestring::<SRID, BigEndian, T>(r, options)
+            } else {
+                read_linestring::<SRID, LittleEndian, T>(r, options)
+            }
+        })
+    }
+}
+
+impl<const SRID: u32, T> WriteEwkb for LineString<SRID, T>
+where
+    T: PointT<SRID>,
+{
+    fn write_ewkb<B, W>(&self, srid: Option<u32>, out: &mut W) -> serialize::Result
+    where
+        B: byteorder::ByteOrder,
+        W: Write,
+    {
+        write_ewkb_header::<B, _, _>(self, srid, out)?;
+        write_linestring_body::<SRID, B, _, _>(self, self.dimension(), out)
     }
 }
 
@@ -62,50 +98,99 @@ where
     T: PointT<SRID> + Debug + EwkbSerializable,
 {
     fn to_sql(&self, out: &mut Output<Pg>) -> serialize::Result {
-        write_linestring(self, out)
+        write_linestring::<SRID, LittleEndian, _, _>(self, out)
     }
 }
 
-pub fn write_linestring<const SRID: u32, T>(
+pub fn write_linestring<const SRID: u32, B, T, W>(
     linestring: &LineString<SRID, T>,
-    out: &mut Output<Pg>,
+    out: &mut W,
 ) -> serialize::Result
 where
+    B: byteorder::ByteOrder,
     T: PointT<SRID> + EwkbSerializable,
+    W: Write,
+{
+    write_ewkb_header::<B, _, _>(linestring, Some(SRID), out)?;
+    write_linestring_body::<SRID, B, _, _>(linestring, linestring.dimension(), out)
+}
+
+pub fn write_linestring_body<const SRID: u32, B, T, W>(
+    linestring: &LineString<SRID, T>,
+    dimension: u32,
+    out: &mut W,
+) -> serialize::Result
+where
+    B: byteorder::ByteOrder,
+    T: PointT<SRID>,
+    W: Write,
 {
-    write_ewkb_header(linestring, Some(SRID), out)?;
     // size and points
-    out.write_u32::<LittleEndian>(linestring.points.len() as u32)?;
+    out.write_u32::<B>(linestring.points.len() as u32)?;
     for point in linestring.points.iter() {
-        write_point_coordinates(point, out)?;
+        write_point_coordinates::<SRID, B, _, _>(point, dimension, out)?;
     }
     Ok(IsNull::No)
 }
 
 fn read_linestring<const SRID: u32, T, P>(
     cursor: &mut Cursor<&[u8]>,
-) -> deserialize::Result<LineString<SRID, P>>
+    options: ReadOptions,
+) -> Result<LineString<SRID, P>, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID> + Clone,
 {
     let g_header = read_ewkb_header::<T>(GeometryType::LineString, cursor)?;
-    check_srid(g_header.srid, SRID)?;
-    read_linestring_body::<SRID, T, P>(g_header.g_type, cursor)
+    g_header.check_srid(SRID, options.policy)?;
+    read_linestring_body::<SRID, T, P>(g_header.g_type, cursor, options)
+}
+
+/// Reads a line nested in a multi line of type `parent_type`, honouring its own byte order.
+pub fn read_nested_linestring<const SRID: u32, P>(
+    parent_type: u32,
+    cursor: &mut Cursor<&[u8]>,
+    options: ReadOptions,
+) -> Result<LineString<SRID, P>, PostgisError>
+where
+    P: PointT<SRID> + Clone,
+{
+    if read_u8(cursor)? == BIG_ENDIAN {
+        read_nested_linestring_body::<SRID, BigEndian, P>(parent_type, cursor, options)
+    } else {
+        read_nested_linestring_body::<SRID, LittleEndian, P>(parent_type, cursor, options)
+    }
+}
+
+fn read_nested_linestring_body<const SRID: u32, T, P>(
+    parent_type: u32,
+    cursor: &mut Cursor<&[u8]>,
+    options: ReadOptions,
+) -> Result<LineString<SRID, P>, PostgisError>
+where
+    T: byteorder::ByteOrder,
+    P: PointT<SRID> + Clone,
+{
+    let g_header = read_nested_header::<T>(Some(GeometryType::LineString), parent_type, cursor)?;
+    read_linestring_body::<SRID, T, P>(g_header.g_type, cursor, options)
 }
 
 pub fn read_linestring_body<const SRID: u32, T, P>(
     g_type: u32,
     cursor: &mut Cursor<&[u8]>,
-) -> deserialize::Result<LineString<SRID, P>>
+    options: ReadOptions,
+) -> Result<LineString<SRID, P>, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID> + Clone,
 {
-    let len = cursor.read_u32::<T>()?;
+    let len = read_count::<T>(cursor, coordinates_size(g_type))?;
     let mut points = Vec::with_capacity(len as usize);
-    for _i in 0..len {
-        points.push(read_point_coordinates::<SRID, T, P>(cursor, g_type)?);
+    for i in 0..len {
+        points.push(
+            read_point_coordinates::<SRID, T, P>(cursor, g_type, options)
+                .map_err(|e| e.nested(i))?,
+        );
     }
     Ok(LineString { points })
 }
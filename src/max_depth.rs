@@ -0,0 +1,117 @@
+use std::{fmt::Debug, ops::Deref};
+
+use diesel::{
+    deserialize::{self, FromSql},
+    pg::{self, Pg},
+    serialize::{self, Output, ToSql},
+};
+
+use crate::{
+    error::PostgisError,
+    ewkb::{ReadEwkb, ReadOptions},
+    sql_types::Geometry,
+    types::{MaxDepth, SridPolicy},
+};
+
+impl<G, const DEPTH: u32> MaxDepth<G, DEPTH> {
+    pub fn new(geometry: G) -> Self {
+        Self { geometry }
+    }
+
+    pub fn into_inner(self) -> G {
+        self.geometry
+    }
+}
+
+impl<G, const DEPTH: u32> From<G> for MaxDepth<G, DEPTH> {
+    fn from(geometry: G) -> Self {
+        Self::new(geometry)
+    }
+}
+
+impl<G, const DEPTH: u32> Deref for MaxDepth<G, DEPTH> {
+    type Target = G;
+
+    fn deref(&self) -> &G {
+        &self.geometry
+    }
+}
+
+impl<G, const DEPTH: u32> FromSql<Geometry, Pg> for MaxDepth<G, DEPTH>
+where
+    G: ReadEwkb,
+{
+    fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
+        Ok(Self::read_ewkb(bytes.as_bytes(), SridPolicy::Strict)?)
+    }
+}
+
+/// Reads `G` with the maximum depth `DEPTH`, in place of the one of the options.
+impl<G, const DEPTH: u32> ReadEwkb for MaxDepth<G, DEPTH>
+where
+    G: ReadEwkb,
+{
+    fn read_ewkb_with(bytes: &[u8], options: ReadOptions) -> Result<Self, PostgisError> {
+        Ok(Self::new(G::read_ewkb_with(
+            bytes,
+            options.with_max_depth(DEPTH),
+        )?))
+    }
+}
+
+impl<G, const DEPTH: u32> ToSql<Geometry, Pg> for MaxDepth<G, DEPTH>
+where
+    G: ToSql<Geometry, Pg> + Debug,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        self.geometry.to_sql(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::LittleEndian;
+
+    use super::*;
+    use crate::{
+        ewkb::{tests::nested_collection, WriteEwkb},
+        types::*,
+    };
+
+    #[test]
+    fn max_depth_test() {
+        let outer = nested_collection();
+        let mut out = Vec::new();
+        outer
+            .write_ewkb::<LittleEndian, _>(Some(4326), &mut out)
+            .unwrap();
+        assert_eq!(
+            Ok(outer.clone()),
+            MaxDepth::<GeometryCollection<4326, Point<4326>>, 3>::read_ewkb(
+                &out,
+                SridPolicy::Strict
+            )
+            .map(MaxDepth::into_inner)
+        );
+        assert!(
+            MaxDepth::<GeometryCollection<4326, Point<4326>>, 2>::read_ewkb(
+                &out,
+                SridPolicy::Strict
+            )
+            .is_err()
+        );
+        // the wrappers override their own option only
+        let mut missing = Vec::new();
+        outer
+            .write_ewkb::<LittleEndian, _>(None, &mut missing)
+            .unwrap();
+        assert_eq!(
+            Ok(outer),
+            MaxDepth::<Lenient<GeometryCollection<4326, Point<4326>>>, 3>::read_ewkb(
+                &missing,
+                SridPolicy::Strict
+            )
+            .map(|g| g.into_inner().into_inner())
+        );
+    }
+}
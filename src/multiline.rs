@@ -1,6 +1,10 @@
-use std::{fmt::Debug, io::Cursor, iter::FromIterator};
+use std::{
+    fmt::Debug,
+    io::{Cursor, Write},
+    iter::FromIterator,
+};
 
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
 use diesel::{
     deserialize::{self, FromSql},
     pg::{self, Pg},
@@ -8,15 +12,22 @@ use diesel::{
 };
 
 use crate::{
-    ewkb::{read_ewkb_header, write_ewkb_header, EwkbSerializable, GeometryType, BIG_ENDIAN},
-    linestring::write_linestring,
-    points::Dimension,
-    types::{LineString, MultiLineString, PointT}, error::check_srid,
+    error::PostgisError,
+    ewkb::{
+        read_all, read_count, read_ewkb_header, read_u8, write_ewkb_header, write_nested_header,
+        EwkbSerializable, GeometryType, ReadEwkb, ReadOptions, WriteEwkb, BIG_ENDIAN,
+        NESTED_GEOMETRY_MIN_SIZE,
+    },
+    linestring::{read_nested_linestring, write_linestring_body},
+    points::points_dimension,
+    types::{LineString, MultiLineString, PointT, SridPolicy},
 };
 
-use crate::{points::read_point_coordinates, sql_types::*};
+use crate::sql_types::*;
 
-impl<const SRID: u32, P: PointT<SRID>> FromIterator<LineString<SRID, P>> for MultiLineString<SRID, P> {
+impl<const SRID: u32, P: PointT<SRID>> FromIterator<LineString<SRID, P>>
+    for MultiLineString<SRID, P>
+{
     fn from_iter<T: IntoIterator<Item = LineString<SRID, P>>>(iter: T) -> Self {
         let lines = iter.into_iter().collect();
         Self { lines }
@@ -31,14 +42,12 @@ where
         MultiLineString { lines: Vec::new() }
     }
 
-    pub fn add_line<'a>(&'a mut self) -> &mut Self {
-        self.lines.push(LineString {
-            points: Vec::new(),
-        });
+    pub fn add_line(&mut self) -> &mut Self {
+        self.lines.push(LineString { points: Vec::new() });
         self
     }
 
-    pub fn add_point<'a>(&'a mut self, point: T) -> &mut Self {
+    pub fn add_point(&mut self, point: T) -> &mut Self {
         if self.lines.last().is_none() {
             self.add_line();
         }
@@ -46,7 +55,7 @@ where
         self
     }
 
-    pub fn add_points<'a>(&'a mut self, points: &[T]) -> &mut Self {
+    pub fn add_points(&mut self, points: &[T]) -> &mut Self {
         if self.lines.last().is_none() {
             self.add_line();
         }
@@ -57,25 +66,26 @@ where
         self
     }
 
+    /// Dimension of the first non-empty point, the one of the point type `T` when there is none.
     pub fn dimension(&self) -> u32 {
-        let mut dimension = Dimension::None as u32;
-        if let Some(line) = self.lines.first() {
-            dimension |= line.dimension();
-        }
-        dimension
+        points_dimension(self.first_point())
+    }
+
+    pub(crate) fn first_point(&self) -> Option<&T> {
+        self.lines.iter().find_map(LineString::first_point)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.iter().all(|line| line.is_empty())
     }
 }
 
 impl<const SRID: u32, T> EwkbSerializable for MultiLineString<SRID, T>
 where
-    T: PointT<SRID>,
+    T: PointT<SRID> + Clone,
 {
     fn geometry_type(&self) -> u32 {
-        let mut g_type = GeometryType::MultiLineString as u32;
-        if let Some(line) = self.lines.first() {
-            g_type |= line.dimension();
-        }
-        g_type
+        GeometryType::MultiLineString as u32 | self.dimension()
     }
 }
 
@@ -84,7 +94,7 @@ where
     T: PointT<SRID> + Debug + PartialEq + EwkbSerializable + Clone,
 {
     fn to_sql(&self, out: &mut Output<Pg>) -> serialize::Result {
-        write_multiline(self, out)
+        write_multiline::<SRID, LittleEndian, _, _>(self, out)
     }
 }
 
@@ -93,63 +103,99 @@ where
     T: PointT<SRID> + Debug + Clone,
 {
     fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
-        let mut r = Cursor::new(bytes.as_bytes());
-        let end = r.read_u8()?;
-        if end == BIG_ENDIAN {
-            read_multiline::<SRID, BigEndian, T>(&mut r)
-        } else {
-            read_multiline::<SRID, LittleEndian, T>(&mut r)
-        }
+        Ok(Self::read_ewkb(bytes.as_bytes(), SridPolicy::Strict)?)
+    }
+}
+
+impl<const SRID: u32, T> ReadEwkb for MultiLineString<SRID, T>
+where
+    T: PointT<SRID> + Clone,
+{
+    fn read_ewkb_with(bytes: &[u8], options: ReadOptions) -> Result<Self, PostgisError> {
+        read_all(bytes, |r| {
+            if read_u8(r)? == BIG_ENDIAN {
+                read_multiline::<SRID, BigEndian, T>(r, options)
+            } else {
+                read_multiline::<SRID, LittleEndian, T>(r, options)
+            }
+        })
+    }
+}
+
+impl<const SRID: u32, T> WriteEwkb for MultiLineString<SRID, T>
+where
+    T: PointT<SRID> + EwkbSerializable + Clone,
+{
+    fn write_ewkb<B, W>(&self, srid: Option<u32>, out: &mut W) -> serialize::Result
+    where
+        B: byteorder::ByteOrder,
+        W: Write,
+    {
+        write_ewkb_header::<B, _, _>(self, srid, out)?;
+        write_multiline_body::<SRID, B, _, _>(self, self.dimension(), out)
     }
 }
 
-pub fn write_multiline<const SRID: u32, T>(
+pub fn write_multiline<const SRID: u32, B, T, W>(
     multiline: &MultiLineString<SRID, T>,
-    out: &mut Output<Pg>,
+    out: &mut W,
 ) -> serialize::Result
 where
+    B: byteorder::ByteOrder,
     T: PointT<SRID> + EwkbSerializable + Clone,
+    W: Write,
 {
-    write_ewkb_header(multiline, Some(SRID), out)?;
-    // number of lines
-    out.write_u32::<LittleEndian>(multiline.lines.len() as u32)?;
+    write_ewkb_header::<B, _, _>(multiline, Some(SRID), out)?;
+    write_multiline_body::<SRID, B, _, _>(multiline, multiline.dimension(), out)
+}
+
+pub fn write_multiline_body<const SRID: u32, B, T, W>(
+    multiline: &MultiLineString<SRID, T>,
+    dimension: u32,
+    out: &mut W,
+) -> serialize::Result
+where
+    B: byteorder::ByteOrder,
+    T: PointT<SRID> + EwkbSerializable + Clone,
+    W: Write,
+{
+    // number of lines, each with its own header but without SRID
+    out.write_u32::<B>(multiline.lines.len() as u32)?;
     for line in multiline.lines.iter() {
-        write_linestring(line, out)?;
+        write_nested_header::<B, _, _>(line, dimension, out)?;
+        write_linestring_body::<SRID, B, _, _>(line, dimension, out)?;
     }
     Ok(IsNull::No)
 }
 
 fn read_multiline<const SRID: u32, T, P>(
     cursor: &mut Cursor<&[u8]>,
-) -> deserialize::Result<MultiLineString<SRID, P>>
+    options: ReadOptions,
+) -> Result<MultiLineString<SRID, P>, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID> + Clone,
 {
     let g_header = read_ewkb_header::<T>(GeometryType::MultiLineString, cursor)?;
-    check_srid(g_header.srid, SRID)?;
-    read_multiline_body::<SRID, T, P>(g_header.g_type, cursor)
+    g_header.check_srid(SRID, options.policy)?;
+    read_multiline_body::<SRID, T, P>(g_header.g_type, cursor, options)
 }
 
 pub fn read_multiline_body<const SRID: u32, T, P>(
     g_type: u32,
     cursor: &mut Cursor<&[u8]>,
-) -> deserialize::Result<MultiLineString<SRID, P>>
+    options: ReadOptions,
+) -> Result<MultiLineString<SRID, P>, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID> + Clone,
 {
-    let lines_n = cursor.read_u32::<T>()?;
+    let lines_n = read_count::<T>(cursor, NESTED_GEOMETRY_MIN_SIZE)?;
     let mut multiline = MultiLineString::new();
-    for _i in 0..lines_n {
-        multiline.add_line();
-        // skip 1 byte for byte order and 4 bytes for point type
-        cursor.read_u8()?;
-        cursor.read_u32::<T>()?;
-        let points_n = cursor.read_u32::<T>()?;
-        for _p in 0..points_n {
-            multiline.add_point(read_point_coordinates::<SRID, T, P>(cursor, g_type)?);
-        }
+    for i in 0..lines_n {
+        let line =
+            read_nested_linestring::<SRID, P>(g_type, cursor, options).map_err(|e| e.nested(i))?;
+        multiline.lines.push(line);
     }
     Ok(multiline)
 }
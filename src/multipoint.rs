@@ -1,19 +1,27 @@
-use std::{fmt::Debug, io::Cursor, iter::FromIterator};
+use std::{
+    fmt::Debug,
+    io::{Cursor, Write},
+    iter::FromIterator,
+};
 
 use crate::{
-    error::check_srid,
-    ewkb::{read_ewkb_header, write_ewkb_header, EwkbSerializable, GeometryType, BIG_ENDIAN},
-    points::{write_point, Dimension},
+    error::PostgisError,
+    ewkb::{
+        coordinates_size, read_all, read_count, read_ewkb_header, read_u8, write_ewkb_header,
+        write_nested_header, EwkbSerializable, GeometryType, ReadEwkb, ReadOptions, WriteEwkb,
+        BIG_ENDIAN,
+    },
+    points::{points_dimension, read_nested_point, write_point_coordinates},
     types::*,
 };
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
 use diesel::{
     deserialize::{self, FromSql},
     pg::{self, Pg},
     serialize::{self, IsNull, Output, ToSql},
 };
 
-use crate::{points::read_point_coordinates, sql_types::*};
+use crate::sql_types::*;
 
 impl<const SRID: u32, P: PointT<SRID>> FromIterator<P> for MultiPoint<SRID, P> {
     fn from_iter<T: IntoIterator<Item = P>>(iter: T) -> Self {
@@ -26,12 +34,17 @@ impl<const SRID: u32, T> MultiPoint<SRID, T>
 where
     T: PointT<SRID>,
 {
+    /// Dimension of the first non-empty point, the one of the point type `T` when there is none.
     pub fn dimension(&self) -> u32 {
-        let mut dimension = Dimension::None as u32;
-        if let Some(point) = self.points.first() {
-            dimension |= point.dimension();
-        }
-        dimension
+        points_dimension(self.first_point())
+    }
+
+    pub(crate) fn first_point(&self) -> Option<&T> {
+        self.points.iter().find(|point| !point.is_empty())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.iter().all(|point| point.is_empty())
     }
 }
 
@@ -40,11 +53,7 @@ where
     T: PointT<SRID>,
 {
     fn geometry_type(&self) -> u32 {
-        let mut g_type = GeometryType::MultiPoint as u32;
-        if let Some(point) = self.points.first() {
-            g_type |= point.dimension();
-        }
-        g_type
+        GeometryType::MultiPoint as u32 | self.dimension()
     }
 }
 
@@ -53,13 +62,36 @@ where
     T: PointT<SRID> + Debug + Clone,
 {
     fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
-        let mut r = Cursor::new(bytes.as_bytes());
-        let end = r.read_u8()?;
-        if end == BIG_ENDIAN {
-            read_multipoint::<SRID, BigEndian, T>(&mut r)
-        } else {
-            read_multipoint::<SRID, LittleEndian, T>(&mut r)
-        }
+        Ok(Self::read_ewkb(bytes.as_bytes(), SridPolicy::Strict)?)
+    }
+}
+
+impl<const SRID: u32, T> ReadEwkb for MultiPoint<SRID, T>
+where
+    T: PointT<SRID> + Clone,
+{
+    fn read_ewkb_with(bytes: &[u8], options: ReadOptions) -> Result<Self, PostgisError> {
+        read_all(bytes, |r| {
+            if read_u8(r)? == BIG_ENDIAN {
+                read_multipoint::<SRID, BigEndian, T>(r, options)
+            } else {
+                read_multipoint::<SRID, LittleEndian, T>(r, options)
+            }
+        })
+    }
+}
+
+impl<const SRID: u32, T> WriteEwkb for MultiPoint<SRID, T>
+where
+    T: PointT<SRID> + EwkbSerializable,
+{
+    fn write_ewkb<B, W>(&self, srid: Option<u32>, out: &mut W) -> serialize::Result
+    where
+        B: byteorder::ByteOrder,
+        W: Write,
+    {
+        write_ewkb_header::<B, _, _>(self, srid, out)?;
+        write_multi_point_body::<SRID, B, _, _>(self, self.dimension(), out)
     }
 }
 
@@ -68,53 +100,69 @@ where
     T: PointT<SRID> + Debug + EwkbSerializable,
 {
     fn to_sql(&self, out: &mut Output<Pg>) -> serialize::Result {
-        write_multi_point(self, out)
+        write_multi_point::<SRID, LittleEndian, _, _>(self, out)
     }
 }
 
-pub fn write_multi_point<const SRID: u32, T>(
+pub fn write_multi_point<const SRID: u32, B, T, W>(
+    multipoint: &MultiPoint<SRID, T>,
+    out: &mut W,
+) -> serialize::Result
+where
+    B: byteorder::ByteOrder,
+    T: PointT<SRID> + EwkbSerializable,
+    W: Write,
+{
+    write_ewkb_header::<B, _, _>(multipoint, Some(SRID), out)?;
+    write_multi_point_body::<SRID, B, _, _>(multipoint, multipoint.dimension(), out)
+}
+
+pub fn write_multi_point_body<const SRID: u32, B, T, W>(
     multipoint: &MultiPoint<SRID, T>,
-    out: &mut Output<Pg>,
+    dimension: u32,
+    out: &mut W,
 ) -> serialize::Result
 where
+    B: byteorder::ByteOrder,
     T: PointT<SRID> + EwkbSerializable,
+    W: Write,
 {
-    write_ewkb_header(multipoint, Some(SRID), out)?;
-    // size and points
-    out.write_u32::<LittleEndian>(multipoint.points.len() as u32)?;
+    // size and points, each with its own header but without SRID
+    out.write_u32::<B>(multipoint.points.len() as u32)?;
     for point in multipoint.points.iter() {
-        write_point(point, out)?;
+        write_nested_header::<B, _, _>(point, dimension, out)?;
+        write_point_coordinates::<SRID, B, _, _>(point, dimension, out)?;
     }
     Ok(IsNull::No)
 }
 
 fn read_multipoint<const SRID: u32, T, P>(
     cursor: &mut Cursor<&[u8]>,
-) -> deserialize::Result<MultiPoint<SRID, P>>
+    options: ReadOptions,
+) -> Result<MultiPoint<SRID, P>, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID> + Clone,
 {
     let g_header = read_ewkb_header::<T>(GeometryType::MultiPoint, cursor)?;
-    check_srid(g_header.srid, SRID)?;
-    read_multi_point_body::<SRID, T, P>(g_header.g_type, cursor)
+    g_header.check_srid(SRID, options.policy)?;
+    read_multi_point_body::<SRID, T, P>(g_header.g_type, cursor, options)
 }
 
 pub fn read_multi_point_body<const SRID: u32, T, P>(
     g_type: u32,
     cursor: &mut Cursor<&[u8]>,
-) -> deserialize::Result<MultiPoint<SRID, P>>
+    options: ReadOptions,
+) -> Result<MultiPoint<SRID, P>, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID> + Clone,
 {
-    let len = cursor.read_u32::<T>()?;
+    let len = read_count::<T>(cursor, 5 + coordinates_size(g_type))?;
     let mut points = Vec::with_capacity(len as usize);
-    for _i in 0..len {
-        // skip 1 byte for byte order and 4 bytes for point type
-        cursor.read_u8()?;
-        cursor.read_u32::<T>()?;
-        points.push(read_point_coordinates::<SRID, T, P>(cursor, g_type)?);
+    for i in 0..len {
+        points
+            .push(read_nested_point::<SRID, P>(g_type, cursor, options).map_err(|e| e.nested(i))?);
     }
     Ok(MultiPoint { points })
 }
@@ -1,13 +1,21 @@
-use std::{fmt::Debug, io::Cursor, iter::FromIterator};
+use std::{
+    fmt::Debug,
+    io::{Cursor, Write},
+    iter::FromIterator,
+};
 
 use crate::{
-    error::check_srid,
-    ewkb::{read_ewkb_header, write_ewkb_header, EwkbSerializable, GeometryType, BIG_ENDIAN},
-    points::Dimension,
-    polygon::{read_polygon_body, write_polygon},
+    error::PostgisError,
+    ewkb::{
+        read_all, read_count, read_ewkb_header, read_u8, write_ewkb_header, write_nested_header,
+        EwkbSerializable, GeometryType, ReadEwkb, ReadOptions, WriteEwkb, BIG_ENDIAN,
+        NESTED_GEOMETRY_MIN_SIZE,
+    },
+    points::points_dimension,
+    polygon::{read_nested_polygon, write_polygon_body},
     types::*,
 };
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
 use diesel::{
     deserialize::{self, FromSql},
     pg::{self, Pg},
@@ -33,12 +41,12 @@ where
         }
     }
 
-    pub fn add_empty_polygon<'a>(&'a mut self) -> &mut Self {
+    pub fn add_empty_polygon(&mut self) -> &mut Self {
         self.polygons.push(Polygon { rings: Vec::new() });
         self
     }
 
-    pub fn add_point<'a>(&'a mut self, point: T) -> &mut Self {
+    pub fn add_point(&mut self, point: T) -> &mut Self {
         if self.polygons.last().is_none() {
             self.add_empty_polygon();
         }
@@ -46,7 +54,7 @@ where
         self
     }
 
-    pub fn add_points<'a>(&'a mut self, points: &[T]) -> &mut Self {
+    pub fn add_points(&mut self, points: &[T]) -> &mut Self {
         if self.polygons.last().is_none() {
             self.add_empty_polygon();
         }
@@ -57,12 +65,17 @@ where
         self
     }
 
+    /// Dimension of the first non-empty point, the one of the point type `T` when there is none.
     pub fn dimension(&self) -> u32 {
-        let mut dimension = Dimension::None as u32;
-        if let Some(polygon) = self.polygons.first() {
-            dimension |= polygon.dimension();
-        }
-        dimension
+        points_dimension(self.first_point())
+    }
+
+    pub(crate) fn first_point(&self) -> Option<&T> {
+        self.polygons.iter().find_map(Polygon::first_point)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.polygons.iter().all(|polygon| polygon.is_empty())
     }
 }
 
@@ -71,11 +84,7 @@ where
     T: PointT<SRID> + Clone,
 {
     fn geometry_type(&self) -> u32 {
-        let mut g_type = GeometryType::MultiPolygon as u32;
-        if let Some(polygon) = self.polygons.first() {
-            g_type |= polygon.dimension();
-        }
-        g_type
+        GeometryType::MultiPolygon as u32 | self.dimension()
     }
 }
 
@@ -84,7 +93,7 @@ where
     T: PointT<SRID> + Debug + PartialEq + Clone + EwkbSerializable,
 {
     fn to_sql(&self, out: &mut Output<Pg>) -> serialize::Result {
-        write_multi_polygon(self, out)
+        write_multi_polygon::<SRID, LittleEndian, _, _>(self, out)
     }
 }
 
@@ -93,62 +102,100 @@ where
     T: PointT<SRID> + Debug + Clone,
 {
     fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
-        let mut r = Cursor::new(bytes.as_bytes());
-        let end = r.read_u8()?;
-        if end == BIG_ENDIAN {
-            read_multi_polygon::<SRID, BigEndian, T>(&mut r)
-        } else {
-            read_multi_polygon::<SRID, LittleEndian, T>(&mut r)
-        }
+        Ok(Self::read_ewkb(bytes.as_bytes(), SridPolicy::Strict)?)
+    }
+}
+
+impl<const SRID: u32, T> ReadEwkb for MultiPolygon<SRID, T>
+where
+    T: PointT<SRID> + Clone,
+{
+    fn read_ewkb_with(bytes: &[u8], options: ReadOptions) -> Result<Self, PostgisError> {
+        read_all(bytes, |r| {
+            if read_u8(r)? == BIG_ENDIAN {
+                read_multi_polygon::<SRID, BigEndian, T>(r, options)
+            } else {
+                read_multi_polygon::<SRID, LittleEndian, T>(r, options)
+            }
+        })
     }
 }
 
-pub fn write_multi_polygon<const SRID: u32, T>(
+impl<const SRID: u32, T> WriteEwkb for MultiPolygon<SRID, T>
+where
+    T: PointT<SRID> + EwkbSerializable + Clone,
+{
+    fn write_ewkb<B, W>(&self, srid: Option<u32>, out: &mut W) -> serialize::Result
+    where
+        B: byteorder::ByteOrder,
+        W: Write,
+    {
+        write_ewkb_header::<B, _, _>(self, srid, out)?;
+        write_multi_polygon_body::<SRID, B, _, _>(self, self.dimension(), out)
+    }
+}
+
+pub fn write_multi_polygon<const SRID: u32, B, T, W>(
+    multipolygon: &MultiPolygon<SRID, T>,
+    out: &mut W,
+) -> serialize::Result
+where
+    B: byteorder::ByteOrder,
+    T: PointT<SRID> + EwkbSerializable + Clone,
+    W: Write,
+{
+    write_ewkb_header::<B, _, _>(multipolygon, Some(SRID), out)?;
+    write_multi_polygon_body::<SRID, B, _, _>(multipolygon, multipolygon.dimension(), out)
+}
+
+pub fn write_multi_polygon_body<const SRID: u32, B, T, W>(
     multipolygon: &MultiPolygon<SRID, T>,
-    out: &mut Output<Pg>,
+    dimension: u32,
+    out: &mut W,
 ) -> serialize::Result
 where
+    B: byteorder::ByteOrder,
     T: PointT<SRID> + EwkbSerializable + Clone,
+    W: Write,
 {
-    write_ewkb_header(multipolygon, Some(SRID), out)?;
-    // number of polygons
-    out.write_u32::<LittleEndian>(multipolygon.polygons.len() as u32)?;
+    // number of polygons, each with its own header but without SRID
+    out.write_u32::<B>(multipolygon.polygons.len() as u32)?;
     for polygon in multipolygon.polygons.iter() {
-        write_polygon(polygon, out)?;
+        write_nested_header::<B, _, _>(polygon, dimension, out)?;
+        write_polygon_body::<SRID, B, _, _>(polygon, dimension, out)?;
     }
     Ok(IsNull::No)
 }
 
 fn read_multi_polygon<const SRID: u32, T, P>(
     cursor: &mut Cursor<&[u8]>,
-) -> deserialize::Result<MultiPolygon<SRID, P>>
+    options: ReadOptions,
+) -> Result<MultiPolygon<SRID, P>, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID> + Clone,
 {
     let g_header = read_ewkb_header::<T>(GeometryType::MultiPolygon, cursor)?;
-    check_srid(g_header.srid, SRID)?;
-    read_multi_polygon_body::<SRID, T, P>(g_header.g_type, cursor)
+    g_header.check_srid(SRID, options.policy)?;
+    read_multi_polygon_body::<SRID, T, P>(g_header.g_type, cursor, options)
 }
 
 pub fn read_multi_polygon_body<const SRID: u32, T, P>(
     g_type: u32,
     cursor: &mut Cursor<&[u8]>,
-) -> deserialize::Result<MultiPolygon<SRID, P>>
+    options: ReadOptions,
+) -> Result<MultiPolygon<SRID, P>, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID> + Clone,
 {
-    let polygons_n = cursor.read_u32::<T>()?;
+    let polygons_n = read_count::<T>(cursor, NESTED_GEOMETRY_MIN_SIZE)?;
     let mut polygon = MultiPolygon::new();
 
-    for _i in 0..polygons_n {
-        // skip 1 byte for byte order and 4 bytes for point type
-        cursor.read_u8()?;
-        cursor.read_u32::<T>()?;
-        polygon
-            .polygons
-            .push(read_polygon_body::<SRID, T, P>(g_type, cursor)?);
+    for i in 0..polygons_n {
+        let nested =
+            read_nested_polygon::<SRID, P>(g_type, cursor, options).map_err(|e| e.nested(i))?;
+        polygon.polygons.push(nested);
     }
     Ok(polygon)
 }
@@ -1,6 +1,8 @@
-use diesel::expression::AsExpression;
-use diesel::sql_types::SqlType;
-use diesel::Expression;
+use diesel::dsl;
+use diesel::expression::{AsExpression, TypedExpressionType};
+use diesel::query_dsl::methods::{LimitDsl, OrderDsl, SelectDsl};
+use diesel::sql_types::{is_nullable, Double, Nullable, SqlType};
+use diesel::{Column, Expression, Table};
 
 diesel::infix_operator!(BBIntersects2D, " && ");
 diesel::infix_operator!(BBOverlapsOrLeft, " &< ");
@@ -16,188 +18,344 @@ diesel::infix_operator!(BBStrictlyAbove, " |>> ");
 diesel::infix_operator!(BBContains, " ~ ");
 diesel::infix_operator!(BBSame, " ~= ");
 
-diesel::infix_operator!(Distance2d, " <-> ");
-diesel::infix_operator!(Distance3dTrajectories, " <-> ");
-diesel::infix_operator!(Distance2BBs, " <#> ");
-diesel::infix_operator!(DistanceNdCentroidsBBs, " <<->> ");
-diesel::infix_operator!(DistanceNdBBs, " <<#>> ");
+diesel::infix_operator!(Distance2d, " <-> ", Double);
+diesel::infix_operator!(Distance3dTrajectories, " <-> ", Double);
+diesel::infix_operator!(Distance2BBs, " <#> ", Double);
+diesel::infix_operator!(DistanceNdCentroidsBBs, " <<->> ", Double);
+diesel::infix_operator!(DistanceNdBBs, " <<#>> ", Double);
+
+/// Marker of [`Operand`] for values and expressions of SQL type `ST`.
+pub struct ValueOperand;
+
+/// Marker of [`Operand`] for expressions whose SQL type is `ST` with the other nullability.
+pub struct ExpressionOperand;
+
+/// Marker of [`Operand`] for `Option` values when `ST` is not nullable.
+pub struct OptionOperand;
+
+/// Right operand of the operators below, `ST` being the SQL type of the left operand.
+///
+/// It is anything which can be used as an expression of type `ST`, such as a column,
+/// `Point<4326>` or any other type implementing `AsExpression<Geometry>`. It can also be an
+/// expression of the other nullability or an `Option` value, so that `Geometry` and
+/// `Nullable<Geometry>` operands can be compared with each other. The result of the operator is
+/// nullable if one of the operands is.
+/// ```
+/// #[macro_use] extern crate diesel;
+/// use diesel::{debug_query, pg::Pg, prelude::*};
+/// use postgis_diesel::{operators::intersects_2d, types::Point};
+///
+/// table! {
+///     use postgis_diesel::sql_types::*;
+///     use diesel::sql_types::*;
+///     places (id) {
+///         id -> Int4,
+///         location -> Geometry,
+///         area -> Nullable<Geometry>,
+///     }
+/// }
+///
+/// let query = places::table
+///     .filter(intersects_2d(places::location, places::area).assume_not_null())
+///     .filter(intersects_2d(places::location, Some(Point::<4326>::new(1.0, 2.0))).assume_not_null())
+///     .select(places::id);
+/// assert_eq!(
+///     debug_query::<Pg, _>(&query).to_string(),
+///     "SELECT \"places\".\"id\" FROM \"places\" \
+///      WHERE (\"places\".\"location\" && \"places\".\"area\" AND \
+///      \"places\".\"location\" && $1) -- binds: [Some(Point { x: 1.0, y: 2.0 })]"
+/// );
+/// ```
+pub trait Operand<ST, Marker> {
+    type Expression: Expression;
+
+    fn into_operand(self) -> Self::Expression;
+}
+
+impl<ST, U> Operand<ST, ValueOperand> for U
+where
+    ST: SqlType + TypedExpressionType,
+    U: AsExpression<ST>,
+{
+    type Expression = U::Expression;
+
+    fn into_operand(self) -> Self::Expression {
+        self.as_expression()
+    }
+}
+
+impl<ST, E> Operand<ST, ExpressionOperand> for E
+where
+    E: Expression,
+    E::SqlType: OtherNullability<ST>,
+{
+    type Expression = E;
+
+    fn into_operand(self) -> E {
+        self
+    }
+}
+
+impl<ST, T> Operand<ST, OptionOperand> for Option<T>
+where
+    ST: SqlType<IsNull = is_nullable::NotNull>,
+    Nullable<ST>: TypedExpressionType,
+    Self: AsExpression<Nullable<ST>>,
+{
+    type Expression = <Self as AsExpression<Nullable<ST>>>::Expression;
+
+    fn into_operand(self) -> Self::Expression {
+        self.as_expression()
+    }
+}
+
+/// Implemented by `Nullable<ST>` for a non-null `ST` and by `ST` for `Nullable<ST>`.
+pub trait OtherNullability<ST> {}
+
+impl<ST> OtherNullability<ST> for Nullable<ST> where ST: SqlType<IsNull = is_nullable::NotNull> {}
+
+impl<ST> OtherNullability<Nullable<ST>> for ST where ST: SqlType<IsNull = is_nullable::NotNull> {}
 
 /// The @ operator returns TRUE if the bounding box of geometry A is completely contained by the bounding box of geometry B.
-pub fn contained_by<T, U>(left: T, right: U) -> BBContainedBy<T, U::Expression>
+pub fn contained_by<T, U, M>(left: T, right: U) -> BBContainedBy<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    BBContainedBy::new(left, right.as_expression())
+    BBContainedBy::new(left, right.into_operand())
 }
 
 /// The ~ operator returns TRUE if the bounding box of geometry A completely contains the bounding box of geometry B.
-pub fn contains<T, U>(left: T, right: U) -> BBContains<T, U::Expression>
+pub fn contains<T, U, M>(left: T, right: U) -> BBContains<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    BBContains::new(left, right.as_expression())
+    BBContains::new(left, right.into_operand())
 }
 
 /// The && operator returns TRUE if the 2D bounding box of geometry A intersects the 2D bounding box of geometry B.
-pub fn intersects_2d<T, U>(left: T, right: U) -> BBIntersects2D<T, U::Expression>
+pub fn intersects_2d<T, U, M>(left: T, right: U) -> BBIntersects2D<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    BBIntersects2D::new(left, right.as_expression())
+    BBIntersects2D::new(left, right.into_operand())
 }
 
 /// The &< operator returns TRUE if the bounding box of geometry A overlaps or is to the left of the bounding box of geometry B, or more accurately, overlaps or is NOT to the right of the bounding box of geometry B.
-pub fn overlaps_or_left<T, U>(left: T, right: U) -> BBOverlapsOrLeft<T, U::Expression>
+pub fn overlaps_or_left<T, U, M>(left: T, right: U) -> BBOverlapsOrLeft<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    BBOverlapsOrLeft::new(left, right.as_expression())
+    BBOverlapsOrLeft::new(left, right.into_operand())
 }
 
 /// The &<| operator returns TRUE if the bounding box of geometry A overlaps or is below of the bounding box of geometry B, or more accurately, overlaps or is NOT above the bounding box of geometry B.
-pub fn overlaps_or_below<T, U>(left: T, right: U) -> BBOverlapsOrBelow<T, U::Expression>
+pub fn overlaps_or_below<T, U, M>(left: T, right: U) -> BBOverlapsOrBelow<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    BBOverlapsOrBelow::new(left, right.as_expression())
+    BBOverlapsOrBelow::new(left, right.into_operand())
 }
 
 /// The &> operator returns TRUE if the bounding box of geometry A overlaps or is to the right of the bounding box of geometry B, or more accurately, overlaps or is NOT to the left of the bounding box of geometry B.
-pub fn overlaps_or_right<T, U>(left: T, right: U) -> BBOverlapsOrRight<T, U::Expression>
+pub fn overlaps_or_right<T, U, M>(left: T, right: U) -> BBOverlapsOrRight<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    BBOverlapsOrRight::new(left, right.as_expression())
+    BBOverlapsOrRight::new(left, right.into_operand())
 }
 
 /// The |&> operator returns TRUE if the bounding box of geometry A overlaps or is above the bounding box of geometry B, or more accurately, overlaps or is NOT below the bounding box of geometry B.
-pub fn overlaps_or_above<T, U>(left: T, right: U) -> BBOverlapsOrAbove<T, U::Expression>
+pub fn overlaps_or_above<T, U, M>(left: T, right: U) -> BBOverlapsOrAbove<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    BBOverlapsOrAbove::new(left, right.as_expression())
+    BBOverlapsOrAbove::new(left, right.into_operand())
 }
 
 /// The << operator returns TRUE if the bounding box of geometry A is strictly to the left of the bounding box of geometry B.
-pub fn strictly_left<T, U>(left: T, right: U) -> BBStrictlyLeft<T, U::Expression>
+pub fn strictly_left<T, U, M>(left: T, right: U) -> BBStrictlyLeft<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    BBStrictlyLeft::new(left, right.as_expression())
+    BBStrictlyLeft::new(left, right.into_operand())
 }
 
 /// The <<| operator returns TRUE if the bounding box of geometry A is strictly below the bounding box of geometry B.
-pub fn strictly_below<T, U>(left: T, right: U) -> BBStrictlyBelow<T, U::Expression>
+pub fn strictly_below<T, U, M>(left: T, right: U) -> BBStrictlyBelow<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    BBStrictlyBelow::new(left, right.as_expression())
+    BBStrictlyBelow::new(left, right.into_operand())
 }
 
 /// The >> operator returns TRUE if the bounding box of geometry A is strictly to the right of the bounding box of geometry B.
-pub fn strictly_right<T, U>(left: T, right: U) -> BBStrictlyRight<T, U::Expression>
+pub fn strictly_right<T, U, M>(left: T, right: U) -> BBStrictlyRight<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    BBStrictlyRight::new(left, right.as_expression())
+    BBStrictlyRight::new(left, right.into_operand())
 }
 
 /// The |>> operator returns TRUE if the bounding box of geometry A is strictly above the bounding box of geometry B.
-pub fn strictly_above<T, U>(left: T, right: U) -> BBStrictlyAbove<T, U::Expression>
+pub fn strictly_above<T, U, M>(left: T, right: U) -> BBStrictlyAbove<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    BBStrictlyAbove::new(left, right.as_expression())
+    BBStrictlyAbove::new(left, right.into_operand())
 }
 
 /// The = operator returns TRUE if the coordinates and coordinate order geometry/geography A are the same as the coordinates and coordinate order of geometry/geography B.
-pub fn g_same<T, U>(left: T, right: U) -> GSame<T, U::Expression>
+pub fn g_same<T, U, M>(left: T, right: U) -> GSame<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    GSame::new(left, right.as_expression())
+    GSame::new(left, right.into_operand())
 }
 
 /// The ~= operator returns TRUE if the bounding box of geometry/geography A is the same as the bounding box of geometry/geography B.
-pub fn bb_same<T, U>(left: T, right: U) -> BBSame<T, U::Expression>
+pub fn bb_same<T, U, M>(left: T, right: U) -> BBSame<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    BBSame::new(left, right.as_expression())
+    BBSame::new(left, right.into_operand())
 }
 
 /// The <-> operator returns the 2D distance between A and B.
-pub fn distance_2d<T, U>(left: T, right: U) -> Distance2d<T, U::Expression>
+pub fn distance_2d<T, U, M>(left: T, right: U) -> Distance2d<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    Distance2d::new(left, right.as_expression())
+    Distance2d::new(left, right.into_operand())
 }
 
 /// The |=| operator returns the 3D distance between two trajectories.
-pub fn distance_3d_trajectories<T, U>(left: T, right: U) -> Distance3dTrajectories<T, U::Expression>
+pub fn distance_3d_trajectories<T, U, M>(
+    left: T,
+    right: U,
+) -> Distance3dTrajectories<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    Distance3dTrajectories::new(left, right.as_expression())
+    Distance3dTrajectories::new(left, right.into_operand())
 }
 
 /// The <#> operator returns the 2D distance between A and B bounding boxes.
-pub fn distance_2d_bbs<T, U>(left: T, right: U) -> Distance2BBs<T, U::Expression>
+pub fn distance_2d_bbs<T, U, M>(left: T, right: U) -> Distance2BBs<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    Distance2BBs::new(left, right.as_expression())
+    Distance2BBs::new(left, right.into_operand())
 }
 
 /// The <<->> operator returns the n-D distance between the centroids of A and B bounding boxes.
-pub fn distance_nd_centroids_bbs<T, U>(left: T, right: U) -> DistanceNdCentroidsBBs<T, U::Expression>
+pub fn distance_nd_centroids_bbs<T, U, M>(
+    left: T,
+    right: U,
+) -> DistanceNdCentroidsBBs<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    DistanceNdCentroidsBBs::new(left, right.as_expression())
+    DistanceNdCentroidsBBs::new(left, right.into_operand())
 }
 
 /// The <<#>> operator returns the n-D distance between A and B bounding boxes.
-pub fn distance_nd_bbs<T, U>(left: T, right: U) -> DistanceNdBBs<T, U::Expression>
+pub fn distance_nd_bbs<T, U, M>(left: T, right: U) -> DistanceNdBBs<T, U::Expression>
 where
     T: Expression,
     <T as diesel::Expression>::SqlType: SqlType,
-    U: AsExpression<T::SqlType>,
+    U: Operand<T::SqlType, M>,
 {
-    DistanceNdBBs::new(left, right.as_expression())
-}
\ No newline at end of file
+    DistanceNdBBs::new(left, right.into_operand())
+}
+
+type NearestSelection<C, E> = (
+    <<C as Column>::Table as Table>::AllColumns,
+    Distance2d<C, E>,
+);
+
+/// The return type of [`nearest`], `E` being the expression of the point.
+pub type Nearest<C, E> = dsl::Limit<
+    dsl::Order<dsl::Select<<C as Column>::Table, NearestSelection<C, E>>, Distance2d<C, E>>,
+>;
+
+/// Selects the `n` rows of the table of `column` nearest to `point` together with their distance,
+/// ordered by `column <-> point` so that a GiST index on `column` is used.
+/// ```
+/// #[macro_use] extern crate diesel;
+/// use diesel::{debug_query, pg::Pg};
+/// use postgis_diesel::{operators::nearest, types::Point};
+///
+/// table! {
+///     use postgis_diesel::sql_types::*;
+///     use diesel::sql_types::*;
+///     places (id) {
+///         id -> Int4,
+///         location -> Geometry,
+///     }
+/// }
+///
+/// let query = nearest(places::location, Point::<4326>::new(1.0, 2.0), 5);
+/// assert_eq!(
+///     debug_query::<Pg, _>(&query).to_string(),
+///     "SELECT \"places\".\"id\", \"places\".\"location\", \"places\".\"location\" <-> $1 \
+///      FROM \"places\" ORDER BY \"places\".\"location\" <-> $2 LIMIT $3 \
+///      -- binds: [Point { x: 1.0, y: 2.0 }, Point { x: 1.0, y: 2.0 }, 5]"
+/// );
+/// // rows load as `((i32, Point<4326>), f64)`
+/// ```
+pub fn nearest<C, U, M>(column: C, point: U, n: i64) -> Nearest<C, U::Expression>
+where
+    C: Column + Expression + Copy,
+    C::SqlType: SqlType,
+    C::Table: Default + SelectDsl<NearestSelection<C, U::Expression>>,
+    NearestSelection<C, U::Expression>: Expression,
+    dsl::Select<C::Table, NearestSelection<C, U::Expression>>:
+        OrderDsl<Distance2d<C, U::Expression>>,
+    dsl::Order<
+        dsl::Select<C::Table, NearestSelection<C, U::Expression>>,
+        Distance2d<C, U::Expression>,
+    >: LimitDsl,
+    U: Operand<C::SqlType, M>,
+    U::Expression: Clone,
+    Distance2d<C, U::Expression>: Expression,
+{
+    let distance = distance_2d(column, point);
+    C::Table::default()
+        .select((C::Table::all_columns(), distance.clone()))
+        .order(distance)
+        .limit(n)
+}
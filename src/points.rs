@@ -1,11 +1,17 @@
-use std::io::Cursor;
+use std::{
+    convert::TryFrom,
+    io::{Cursor, Write},
+};
 
 use crate::{
-    error::check_srid,
-    ewkb::{read_ewkb_header, EwkbSerializable, GeometryType, BIG_ENDIAN},
+    error::{ErrorLocation, PostgisError},
+    ewkb::{
+        invalid_geometry, read_all, read_ewkb_header, read_f64, read_nested_header, read_u8,
+        EwkbSerializable, GeometryType, ReadEwkb, ReadOptions, WriteEwkb, BIG_ENDIAN,
+    },
     types::*,
 };
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
 use diesel::{
     deserialize::{self, FromSql},
     pg::{self, Pg},
@@ -14,6 +20,9 @@ use diesel::{
 
 use crate::{ewkb::write_ewkb_header, sql_types::*};
 
+/// Coordinates of a geometry besides X and Y, as flagged in its EWKB type code.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Dimension {
     None = 0,
     Z = 0x80000000,
@@ -21,6 +30,19 @@ pub enum Dimension {
     ZM = 0x40000000 | 0x80000000,
 }
 
+impl From<u32> for Dimension {
+    fn from(g_type: u32) -> Self {
+        let z = g_type & Dimension::Z as u32 == Dimension::Z as u32;
+        let m = g_type & Dimension::M as u32 == Dimension::M as u32;
+        match (z, m) {
+            (true, true) => Dimension::ZM,
+            (true, false) => Dimension::Z,
+            (false, true) => Dimension::M,
+            (false, false) => Dimension::None,
+        }
+    }
+}
+
 impl<const SRID: u32> EwkbSerializable for Point<SRID> {
     fn geometry_type(&self) -> u32 {
         GeometryType::Point as u32
@@ -45,6 +67,12 @@ impl<const SRID: u32> EwkbSerializable for PointZM<SRID> {
     }
 }
 
+impl<const SRID: u32> EwkbSerializable for PointAny<SRID> {
+    fn geometry_type(&self) -> u32 {
+        GeometryType::Point as u32 | self.dimension()
+    }
+}
+
 impl<const SRID: u32> Point<SRID> {
     pub fn new(x: f64, y: f64) -> Self {
         Self { x, y }
@@ -242,17 +270,101 @@ impl<const SRID: u32> PointT<SRID> for PointZM<SRID> {
     }
 }
 
+impl<const SRID: u32> PointAny<SRID> {
+    pub fn new(x: f64, y: f64, z: Option<f64>, m: Option<f64>) -> Self {
+        Self { x, y, z, m }
+    }
+}
+
+impl<const SRID: u32> PointT<SRID> for PointAny<SRID> {
+    fn get_x(&self) -> f64 {
+        self.x
+    }
+
+    fn get_y(&self) -> f64 {
+        self.y
+    }
+
+    fn get_z(&self) -> Option<f64> {
+        self.z
+    }
+
+    fn get_m(&self) -> Option<f64> {
+        self.m
+    }
+
+    fn dimension(&self) -> u32 {
+        let mut dimension = Dimension::None as u32;
+        if self.z.is_some() {
+            dimension |= Dimension::Z as u32;
+        }
+        if self.m.is_some() {
+            dimension |= Dimension::M as u32;
+        }
+        dimension
+    }
+
+    fn new_point(
+        x: f64,
+        y: f64,
+        z: Option<f64>,
+        m: Option<f64>,
+    ) -> Result<Self, PointConstructorError> {
+        Ok(PointAny { x, y, z, m })
+    }
+}
+
+macro_rules! impl_point_any_conversions {
+    ($p:ident) => {
+        impl<const SRID: u32> From<$p<SRID>> for PointAny<SRID> {
+            fn from(point: $p<SRID>) -> Self {
+                PointAny::new(point.get_x(), point.get_y(), point.get_z(), point.get_m())
+            }
+        }
+
+        impl<const SRID: u32> TryFrom<PointAny<SRID>> for $p<SRID> {
+            type Error = PointConstructorError;
+
+            fn try_from(point: PointAny<SRID>) -> Result<Self, Self::Error> {
+                $p::new_point(point.x, point.y, point.z, point.m)
+            }
+        }
+    };
+}
+
+impl_point_any_conversions!(Point);
+impl_point_any_conversions!(PointZ);
+impl_point_any_conversions!(PointM);
+impl_point_any_conversions!(PointZM);
+
 macro_rules! impl_point_from_sql {
     ($p:ident) => {
         impl<const SRID: u32> FromSql<Geometry, Pg> for $p<SRID> {
             fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
-                let mut r = Cursor::new(bytes.as_bytes());
-                let end = r.read_u8()?;
-                if end == BIG_ENDIAN {
-                    read_point::<SRID, BigEndian, $p<SRID>>(&mut r)
-                } else {
-                    read_point::<SRID, LittleEndian, $p<SRID>>(&mut r)
-                }
+                Ok(Self::read_ewkb(bytes.as_bytes(), SridPolicy::Strict)?)
+            }
+        }
+
+        impl<const SRID: u32> ReadEwkb for $p<SRID> {
+            fn read_ewkb_with(bytes: &[u8], options: ReadOptions) -> Result<Self, PostgisError> {
+                read_all(bytes, |r| {
+                    if read_u8(r)? == BIG_ENDIAN {
+                        read_point::<SRID, BigEndian, $p<SRID>>(r, options)
+                    } else {
+                        read_point::<SRID, LittleEndian, $p<SRID>>(r, options)
+                    }
+                })
+            }
+        }
+
+        impl<const SRID: u32> WriteEwkb for $p<SRID> {
+            fn write_ewkb<B, W>(&self, srid: Option<u32>, out: &mut W) -> serialize::Result
+            where
+                B: byteorder::ByteOrder,
+                W: Write,
+            {
+                write_ewkb_header::<B, _, _>(self, srid, out)?;
+                write_point_coordinates::<SRID, B, _, _>(self, self.dimension(), out)
             }
         }
     };
@@ -262,89 +374,271 @@ impl_point_from_sql!(Point);
 impl_point_from_sql!(PointZ);
 impl_point_from_sql!(PointM);
 impl_point_from_sql!(PointZM);
+impl_point_from_sql!(PointAny);
 
 impl<const SRID: u32> ToSql<Geometry, Pg> for Point<SRID> {
     fn to_sql(&self, out: &mut Output<Pg>) -> serialize::Result {
-        write_point(self, out)?;
+        write_point::<SRID, LittleEndian, _, _>(self, out)?;
         Ok(IsNull::No)
     }
 }
 
 impl<const SRID: u32> ToSql<Geometry, Pg> for PointZ<SRID> {
     fn to_sql(&self, out: &mut Output<Pg>) -> serialize::Result {
-        write_point(self, out)?;
+        write_point::<SRID, LittleEndian, _, _>(self, out)?;
         Ok(IsNull::No)
     }
 }
 
 impl<const SRID: u32> ToSql<Geometry, Pg> for PointM<SRID> {
     fn to_sql(&self, out: &mut Output<Pg>) -> serialize::Result {
-        write_point(self, out)?;
+        write_point::<SRID, LittleEndian, _, _>(self, out)?;
         Ok(IsNull::No)
     }
 }
 
 impl<const SRID: u32> ToSql<Geometry, Pg> for PointZM<SRID> {
     fn to_sql(&self, out: &mut Output<Pg>) -> serialize::Result {
-        write_point(self, out)?;
+        write_point::<SRID, LittleEndian, _, _>(self, out)?;
         Ok(IsNull::No)
     }
 }
 
-pub fn write_point<const SRID: u32, T>(point: &T, out: &mut Output<Pg>) -> serialize::Result
+impl<const SRID: u32> ToSql<Geometry, Pg> for PointAny<SRID> {
+    fn to_sql(&self, out: &mut Output<Pg>) -> serialize::Result {
+        write_point::<SRID, LittleEndian, _, _>(self, out)?;
+        Ok(IsNull::No)
+    }
+}
+
+pub fn write_point<const SRID: u32, B, T, W>(point: &T, out: &mut W) -> serialize::Result
 where
+    B: byteorder::ByteOrder,
     T: PointT<SRID> + EwkbSerializable,
+    W: Write,
 {
-    write_ewkb_header(point, Some(SRID), out)?;
-    write_point_coordinates(point, out)?;
+    write_ewkb_header::<B, _, _>(point, Some(SRID), out)?;
+    write_point_coordinates::<SRID, B, _, _>(point, point.dimension(), out)?;
     Ok(IsNull::No)
 }
 
-pub fn write_point_coordinates<const SRID: u32, T>(
+/// Writes the coordinates of `point` in a geometry of dimension `dimension`, which a non-empty
+/// point must have. An empty point is written with NaN coordinates of that dimension, whatever its
+/// own.
+pub fn write_point_coordinates<const SRID: u32, B, T, W>(
     point: &T,
-    out: &mut Output<Pg>,
+    dimension: u32,
+    out: &mut W,
 ) -> serialize::Result
 where
+    B: byteorder::ByteOrder,
     T: PointT<SRID>,
+    W: Write,
 {
-    out.write_f64::<LittleEndian>(point.get_x())?;
-    out.write_f64::<LittleEndian>(point.get_y())?;
-    if point.get_z().is_some() {
-        out.write_f64::<LittleEndian>(point.get_z().unwrap())?;
+    let empty = point.is_empty();
+    if !empty && point.dimension() != dimension {
+        return Err(PostgisError::DimensionMismatch {
+            expected: Dimension::from(dimension),
+            found: Dimension::from(point.dimension()),
+            location: ErrorLocation::default(),
+        }
+        .into());
+    }
+    let coordinate = |c: Option<f64>| if empty { Some(f64::NAN) } else { c };
+    out.write_f64::<B>(point.get_x())?;
+    out.write_f64::<B>(point.get_y())?;
+    if dimension & Dimension::Z as u32 == Dimension::Z as u32 {
+        if let Some(z) = coordinate(point.get_z()) {
+            out.write_f64::<B>(z)?;
+        }
     }
-    if point.get_m().is_some() {
-        out.write_f64::<LittleEndian>(point.get_m().unwrap())?;
+    if dimension & Dimension::M as u32 == Dimension::M as u32 {
+        if let Some(m) = coordinate(point.get_m()) {
+            out.write_f64::<B>(m)?;
+        }
     }
     Ok(IsNull::No)
 }
 
-fn read_point<const SRID: u32, T, P>(cursor: &mut Cursor<&[u8]>) -> deserialize::Result<P>
+/// Dimension of `point`, the first non-empty point of a geometry, or of the point type `T` when
+/// the geometry has none. All geometries take the dimension of their points this way.
+pub fn points_dimension<const SRID: u32, T>(point: Option<&T>) -> u32
+where
+    T: PointT<SRID>,
+{
+    match point {
+        Some(point) => point.dimension(),
+        None => T::default().dimension(),
+    }
+}
+
+fn read_point<const SRID: u32, T, P>(
+    cursor: &mut Cursor<&[u8]>,
+    options: ReadOptions,
+) -> Result<P, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID>,
 {
     let g_header = read_ewkb_header::<T>(GeometryType::Point, cursor)?;
-    check_srid(g_header.srid, SRID)?;
-    read_point_coordinates::<SRID, T, P>(cursor, g_header.g_type)
+    g_header.check_srid(SRID, options.policy)?;
+    read_point_coordinates::<SRID, T, P>(cursor, g_header.g_type, options)
+}
+
+/// Reads a point nested in a multi point of type `parent_type`, honouring its own byte order.
+pub fn read_nested_point<const SRID: u32, P>(
+    parent_type: u32,
+    cursor: &mut Cursor<&[u8]>,
+    options: ReadOptions,
+) -> Result<P, PostgisError>
+where
+    P: PointT<SRID>,
+{
+    if read_u8(cursor)? == BIG_ENDIAN {
+        read_nested_point_body::<SRID, BigEndian, P>(parent_type, cursor, options)
+    } else {
+        read_nested_point_body::<SRID, LittleEndian, P>(parent_type, cursor, options)
+    }
+}
+
+fn read_nested_point_body<const SRID: u32, T, P>(
+    parent_type: u32,
+    cursor: &mut Cursor<&[u8]>,
+    options: ReadOptions,
+) -> Result<P, PostgisError>
+where
+    T: byteorder::ByteOrder,
+    P: PointT<SRID>,
+{
+    let g_header = read_nested_header::<T>(Some(GeometryType::Point), parent_type, cursor)?;
+    read_point_coordinates::<SRID, T, P>(cursor, g_header.g_type, options)
 }
 
 pub fn read_point_coordinates<const SRID: u32, T, P>(
     cursor: &mut Cursor<&[u8]>,
     g_type: u32,
-) -> deserialize::Result<P>
+    options: ReadOptions,
+) -> Result<P, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID>,
 {
-    let x = cursor.read_f64::<T>()?;
-    let y = cursor.read_f64::<T>()?;
+    let offset = cursor.position();
+    let x = read_f64::<T>(cursor)?;
+    let y = read_f64::<T>(cursor)?;
     let mut z = None;
     if g_type & Dimension::Z as u32 == Dimension::Z as u32 {
-        z = Some(cursor.read_f64::<T>()?);
+        z = Some(read_f64::<T>(cursor)?);
     }
     let mut m = None;
     if g_type & Dimension::M as u32 == Dimension::M as u32 {
-        m = Some(cursor.read_f64::<T>()?);
+        m = Some(read_f64::<T>(cursor)?);
+    }
+    if let Some(dimension) = options.dimension {
+        // coordinates of an empty point are all NaN
+        let missing = if x.is_nan() && y.is_nan() {
+            f64::NAN
+        } else {
+            0.0
+        };
+        let force = |c: Option<f64>, flag: Dimension| {
+            if dimension as u32 & flag as u32 == flag as u32 {
+                Some(c.unwrap_or(missing))
+            } else {
+                None
+            }
+        };
+        z = force(z, Dimension::Z);
+        m = force(m, Dimension::M);
+    }
+    P::new_point(x, y, z, m).map_err(invalid_geometry(offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ewkb::tests::{bytes, MULTI_POINT},
+        multipoint::write_multi_point,
+    };
+
+    #[test]
+    fn point_any_test() {
+        let multi_point = bytes(MULTI_POINT);
+        let read = MultiPoint::<4326, PointAny<4326>>::read_ewkb(&multi_point, SridPolicy::Strict)
+            .unwrap();
+        assert_eq!(
+            vec![
+                PointAny::new(1.0, 2.0, None, None),
+                PointAny::new(3.0, 4.0, None, None)
+            ],
+            read.points
+        );
+        let mut out = Vec::new();
+        write_multi_point::<4326, LittleEndian, _, _>(&read, &mut out).unwrap();
+        assert_eq!(multi_point, out);
+
+        let point = PointZM::<4326>::new(1.0, 2.0, 3.0, 4.0);
+        let mut out = Vec::new();
+        write_point::<4326, LittleEndian, _, _>(&point, &mut out).unwrap();
+        let read = PointAny::<4326>::read_ewkb(&out, SridPolicy::Strict).unwrap();
+        assert_eq!(PointAny::from(point), read);
+        assert_eq!(Ok(point), PointZM::try_from(read));
+        assert!(PointZ::try_from(read).is_err());
+
+        // points of different dimensions can't be written in the same geometry
+        let mixed = GeometryCollection::<4326, PointAny<4326>> {
+            geometries: vec![
+                GeometryContainer::Point(PointAny::new(1.0, 2.0, None, None)),
+                GeometryContainer::LineString(LineString {
+                    points: vec![PointAny::new(1.0, 2.0, Some(3.0), None)],
+                }),
+            ],
+        };
+        let err = mixed
+            .write_ewkb::<LittleEndian, _>(Some(4326), &mut Vec::new())
+            .unwrap_err();
+        assert_eq!(
+            Some(&PostgisError::DimensionMismatch {
+                expected: Dimension::None,
+                found: Dimension::Z,
+                location: ErrorLocation::default(),
+            }),
+            err.downcast_ref()
+        );
+    }
+
+    #[test]
+    fn empty_point_any_test() {
+        // empty points are written with the dimension of their geometry
+        let any = MultiPoint::<4326, PointAny<4326>> {
+            points: vec![PointAny::empty(), PointAny::new(1.0, 2.0, Some(3.0), None)],
+        };
+        assert_eq!(Dimension::Z as u32, any.dimension());
+        let mut out = Vec::new();
+        any.write_ewkb::<LittleEndian, _>(Some(4326), &mut out)
+            .unwrap();
+        let read = MultiPoint::<4326, PointAny<4326>>::read_ewkb(&out, SridPolicy::Strict).unwrap();
+        assert!(read.points[0].is_empty());
+        assert_eq!(Dimension::Z as u32, read.points[0].dimension());
+        assert_eq!(any.points[1], read.points[1]);
+        let line = LineString::<4326, PointAny<4326>> {
+            points: any.points.clone(),
+        };
+        assert_eq!(Dimension::Z as u32, line.dimension());
+        let collection = GeometryCollection::<4326, PointAny<4326>> {
+            geometries: vec![
+                GeometryContainer::Point(PointAny::empty()),
+                GeometryContainer::LineString(line),
+            ],
+        };
+        assert_eq!(Dimension::Z as u32, collection.dimension());
+        let mut out = Vec::new();
+        collection
+            .write_ewkb::<LittleEndian, _>(Some(4326), &mut out)
+            .unwrap();
+        let read = GeometryCollection::<4326, PointAny<4326>>::read_ewkb(&out, SridPolicy::Strict)
+            .unwrap();
+        assert_eq!(Dimension::Z as u32, read.geometries[0].dimension());
     }
-    Ok(P::new_point(x, y, z, m)?)
 }
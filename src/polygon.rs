@@ -1,17 +1,25 @@
 use crate::{
-    error::check_srid,
-    ewkb::{read_ewkb_header, write_ewkb_header, EwkbSerializable, GeometryType, BIG_ENDIAN},
-    points::{read_point_coordinates, write_point_coordinates, Dimension},
+    error::PostgisError,
+    ewkb::{
+        coordinates_size, read_all, read_count, read_ewkb_header, read_nested_header, read_u8,
+        write_ewkb_header, EwkbSerializable, GeometryType, ReadEwkb, ReadOptions, WriteEwkb,
+        BIG_ENDIAN,
+    },
+    points::{points_dimension, read_point_coordinates, write_point_coordinates},
     sql_types::*,
-    types::{LineString, PointT, Polygon},
+    types::{LineString, PointT, Polygon, SridPolicy},
 };
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{BigEndian, LittleEndian, WriteBytesExt};
 use diesel::{
     deserialize::{self, FromSql},
     pg::{self, Pg},
     serialize::{self, IsNull, Output, ToSql},
 };
-use std::{fmt::Debug, io::Cursor, iter::FromIterator};
+use std::{
+    fmt::Debug,
+    io::{Cursor, Write},
+    iter::FromIterator,
+};
 
 impl<const SRID: u32, P: PointT<SRID>> Default for Polygon<SRID, P> {
     fn default() -> Self {
@@ -36,12 +44,12 @@ where
         Polygon { rings: Vec::new() }
     }
 
-    pub fn add_ring<'a>(&'a mut self) -> &mut Self {
+    pub fn add_ring(&mut self) -> &mut Self {
         self.rings.push(LineString::default());
         self
     }
 
-    pub fn add_point<'a>(&'a mut self, point: T) -> &mut Self {
+    pub fn add_point(&mut self, point: T) -> &mut Self {
         if self.rings.last().is_none() {
             self.add_ring();
         }
@@ -49,7 +57,7 @@ where
         self
     }
 
-    pub fn add_points<'a>(&'a mut self, points: &[T]) -> &mut Self {
+    pub fn add_points(&mut self, points: &[T]) -> &mut Self {
         if self.rings.last().is_none() {
             self.add_ring();
         }
@@ -60,14 +68,17 @@ where
         self
     }
 
+    /// Dimension of the first non-empty point, the one of the point type `T` when there is none.
     pub fn dimension(&self) -> u32 {
-        let mut dimension = Dimension::None as u32;
-        if let Some(ring) = self.rings.first() {
-            if let Some(point) = ring.points.first() {
-                dimension |= point.dimension();
-            }
-        }
-        dimension
+        points_dimension(self.first_point())
+    }
+
+    pub(crate) fn first_point(&self) -> Option<&T> {
+        self.rings.iter().find_map(LineString::first_point)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rings.iter().all(|ring| ring.points.is_empty())
     }
 }
 
@@ -85,25 +96,40 @@ where
     T: PointT<SRID> + Debug + PartialEq + Clone + EwkbSerializable,
 {
     fn to_sql(&self, out: &mut Output<Pg>) -> serialize::Result {
-        write_polygon(self, out)
+        write_polygon::<SRID, LittleEndian, _, _>(self, out)
     }
 }
 
-pub fn write_polygon<const SRID: u32, T>(
+pub fn write_polygon<const SRID: u32, B, T, W>(
     polygon: &Polygon<SRID, T>,
-    out: &mut Output<Pg>,
+    out: &mut W,
 ) -> serialize::Result
 where
+    B: byteorder::ByteOrder,
     T: PointT<SRID> + EwkbSerializable + Clone,
+    W: Write,
+{
+    write_ewkb_header::<B, _, _>(polygon, Some(SRID), out)?;
+    write_polygon_body::<SRID, B, _, _>(polygon, polygon.dimension(), out)
+}
+
+pub fn write_polygon_body<const SRID: u32, B, T, W>(
+    polygon: &Polygon<SRID, T>,
+    dimension: u32,
+    out: &mut W,
+) -> serialize::Result
+where
+    B: byteorder::ByteOrder,
+    T: PointT<SRID>,
+    W: Write,
 {
-    write_ewkb_header(polygon, Some(SRID), out)?;
     // number of rings
-    out.write_u32::<LittleEndian>(polygon.rings.len() as u32)?;
+    out.write_u32::<B>(polygon.rings.len() as u32)?;
     for ring in polygon.rings.iter() {
         //number of points in ring
-        out.write_u32::<LittleEndian>(ring.points.len() as u32)?;
+        out.write_u32::<B>(ring.points.len() as u32)?;
         for point in ring.points.iter() {
-            write_point_coordinates(point, out)?;
+            write_point_coordinates::<SRID, B, _, _>(point, dimension, out)?;
         }
     }
     Ok(IsNull::No)
@@ -114,43 +140,101 @@ where
     T: PointT<SRID> + Debug + Clone,
 {
     fn from_sql(bytes: pg::PgValue) -> deserialize::Result<Self> {
-        let mut r = Cursor::new(bytes.as_bytes());
-        let end = r.read_u8()?;
-        if end == BIG_ENDIAN {
-            read_polygon::<SRID, BigEndian, T>(&mut r)
-        } else {
-            read_polygon::<SRID, LittleEndian, T>(&mut r)
-        }
+        Ok(Self::read_ewkb(bytes.as_bytes(), SridPolicy::Strict)?)
+    }
+}
+
+impl<const SRID: u32, T> ReadEwkb for Polygon<SRID, T>
+where
+    T: PointT<SRID> + Clone,
+{
+    fn read_ewkb_with(bytes: &[u8], options: ReadOptions) -> Result<Self, PostgisError> {
+        read_all(bytes, |r| {
+            if read_u8(r)? == BIG_ENDIAN {
+                read_polygon::<SRID, BigEndian, T>(r, options)
+            } else {
+                read_polygon::<SRID, LittleEndian, T>(r, options)
+            }
+        })
+    }
+}
+
+impl<const SRID: u32, T> WriteEwkb for Polygon<SRID, T>
+where
+    T: PointT<SRID> + Clone,
+{
+    fn write_ewkb<B, W>(&self, srid: Option<u32>, out: &mut W) -> serialize::Result
+    where
+        B: byteorder::ByteOrder,
+        W: Write,
+    {
+        write_ewkb_header::<B, _, _>(self, srid, out)?;
+        write_polygon_body::<SRID, B, _, _>(self, self.dimension(), out)
     }
 }
 
 fn read_polygon<const SRID: u32, T, P>(
     cursor: &mut Cursor<&[u8]>,
-) -> deserialize::Result<Polygon<SRID, P>>
+    options: ReadOptions,
+) -> Result<Polygon<SRID, P>, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID> + Clone,
 {
     let g_header = read_ewkb_header::<T>(GeometryType::Polygon, cursor)?;
-    check_srid(g_header.srid, SRID)?;
-    read_polygon_body::<SRID, T, P>(g_header.g_type, cursor)
+    g_header.check_srid(SRID, options.policy)?;
+    read_polygon_body::<SRID, T, P>(g_header.g_type, cursor, options)
+}
+
+/// Reads a polygon nested in a multi polygon of type `parent_type`, honouring its own byte order.
+pub fn read_nested_polygon<const SRID: u32, P>(
+    parent_type: u32,
+    cursor: &mut Cursor<&[u8]>,
+    options: ReadOptions,
+) -> Result<Polygon<SRID, P>, PostgisError>
+where
+    P: PointT<SRID> + Clone,
+{
+    if read_u8(cursor)? == BIG_ENDIAN {
+        read_nested_polygon_body::<SRID, BigEndian, P>(parent_type, cursor, options)
+    } else {
+        read_nested_polygon_body::<SRID, LittleEndian, P>(parent_type, cursor, options)
+    }
+}
+
+fn read_nested_polygon_body<const SRID: u32, T, P>(
+    parent_type: u32,
+    cursor: &mut Cursor<&[u8]>,
+    options: ReadOptions,
+) -> Result<Polygon<SRID, P>, PostgisError>
+where
+    T: byteorder::ByteOrder,
+    P: PointT<SRID> + Clone,
+{
+    let g_header = read_nested_header::<T>(Some(GeometryType::Polygon), parent_type, cursor)?;
+    read_polygon_body::<SRID, T, P>(g_header.g_type, cursor, options)
 }
 
 pub fn read_polygon_body<const SRID: u32, T, P>(
     g_type: u32,
     cursor: &mut Cursor<&[u8]>,
-) -> deserialize::Result<Polygon<SRID, P>>
+    options: ReadOptions,
+) -> Result<Polygon<SRID, P>, PostgisError>
 where
     T: byteorder::ByteOrder,
     P: PointT<SRID> + Clone,
 {
-    let rings_n = cursor.read_u32::<T>()?;
+    let rings_n = read_count::<T>(cursor, 4)?;
     let mut polygon = Polygon::new();
-    for _i in 0..rings_n {
+    for i in 0..rings_n {
         polygon.add_ring();
-        let points_n = cursor.read_u32::<T>()?;
-        for _p in 0..points_n {
-            polygon.add_point(read_point_coordinates::<SRID, T, P>(cursor, g_type)?);
+        let points_n =
+            read_count::<T>(cursor, coordinates_size(g_type)).map_err(|e| e.nested(i))?;
+        for p in 0..points_n {
+            polygon.add_point(
+                read_point_coordinates::<SRID, T, P>(cursor, g_type, options)
+                    .map_err(|e| e.nested(p).nested(i))?,
+            );
         }
     }
     Ok(polygon)
@@ -1,3 +1,11 @@
+use diesel::{
+    expression::{CastsTo, FallibleCastsTo, KnownCastSqlTypeName},
+    pg::Pg,
+    sql_types::{
+        Array, Integer, MaybeNullableType, Nullable, OneIsNullable, Record, SingleValue, Text,
+    },
+};
+
 /// SQL types which may be used in table definition.
 /// ```
 ///#[macro_use] extern crate diesel;
@@ -11,6 +19,168 @@
 ///    }
 ///}
 /// ```
-#[derive(SqlType, QueryId)]
+#[derive(Debug, Clone, Copy, Default, SqlType, QueryId)]
 #[diesel(postgres_type(name = "geometry"))]
 pub struct Geometry;
+
+/// Geodetic counterpart of [`Geometry`], measurements on it are done in meters on the spheroid.
+/// ```
+///#[macro_use] extern crate diesel;
+///table! {
+///    use postgis_diesel::sql_types::*;
+///    use diesel::sql_types::*;
+///    geography_example (id) {
+///        id -> Int4,
+///        area -> Geography,
+///    }
+///}
+/// ```
+#[derive(Debug, Clone, Copy, Default, SqlType, QueryId)]
+#[diesel(postgres_type(name = "geography"))]
+pub struct Geography;
+
+/// 2D bounding box, returned for example by `ST_Extent`.
+///
+/// PostGIS has no binary representation for `box2d`, so values of that type can't be bound or
+/// loaded directly, cast them to [`Geometry`] instead. Boxes carry no SRID, so the casted
+/// geometry has an unknown SRID until it is set with `ST_SetSRID`:
+/// ```
+/// #[macro_use] extern crate diesel;
+/// use diesel::{prelude::*, sql_types::Nullable};
+/// use postgis_diesel::{functions::{st_extent, st_set_srid}, sql_types::Geometry};
+/// table! {
+///     use postgis_diesel::sql_types::*;
+///     use diesel::sql_types::*;
+///     geometry_example (id) {
+///         id -> Int4,
+///         polygon -> Geometry,
+///     }
+/// }
+/// let query = geometry_example::table.select(st_set_srid(
+///     st_extent(geometry_example::polygon)
+///         .cast::<Nullable<Geometry>>()
+///         .assume_not_null(),
+///     4326,
+/// ));
+/// ```
+#[derive(Debug, Clone, Copy, Default, SqlType, QueryId)]
+#[diesel(postgres_type(name = "box2d"))]
+pub struct Box2D;
+
+/// 3D bounding box, returned for example by `ST_3DExtent`. Like [`Box2D`] it has to be cast to
+/// [`Geometry`] to be loaded.
+#[derive(Debug, Clone, Copy, Default, SqlType, QueryId)]
+#[diesel(postgres_type(name = "box3d"))]
+pub struct Box3D;
+
+/// [`Geometry`] or `Nullable<Geometry>`, spatial functions and operators accept both.
+pub trait GeometryOrNullable: SingleValue {}
+
+impl GeometryOrNullable for Geometry {}
+impl GeometryOrNullable for Nullable<Geometry> {}
+
+/// [`Geography`] or `Nullable<Geography>`, spatial functions and operators accept both.
+pub trait GeographyOrNullable: SingleValue {}
+
+impl GeographyOrNullable for Geography {}
+impl GeographyOrNullable for Nullable<Geography> {}
+
+/// `O`, nullable if `Self` is nullable. PostGIS functions return NULL for a NULL argument, so
+/// this is the return type of functions taking one geometry.
+pub trait MaybeNullableValue<O>: SingleValue {
+    type Out: SingleValue;
+}
+
+impl<T, O> MaybeNullableValue<O> for T
+where
+    T: SingleValue,
+    T::IsNull: MaybeNullableType<O>,
+    <T::IsNull as MaybeNullableType<O>>::Out: SingleValue,
+{
+    type Out = <T::IsNull as MaybeNullableType<O>>::Out;
+}
+
+/// `O`, nullable if `Self` or `T` is nullable, the return type of functions taking two
+/// geometries.
+pub trait CombinedNullableValue<T, O>: SingleValue {
+    type Out: SingleValue;
+}
+
+impl<T1, T2, O> CombinedNullableValue<T2, O> for T1
+where
+    T1: SingleValue,
+    T2: SingleValue,
+    T1::IsNull: OneIsNullable<T2::IsNull>,
+    <T1::IsNull as OneIsNullable<T2::IsNull>>::Out: MaybeNullableType<O>,
+    <<T1::IsNull as OneIsNullable<T2::IsNull>>::Out as MaybeNullableType<O>>::Out: SingleValue,
+{
+    type Out = <<T1::IsNull as OneIsNullable<T2::IsNull>>::Out as MaybeNullableType<O>>::Out;
+}
+
+/// PostGIS `geometry_dump` composite type, the rows produced by `ST_Dump`, `ST_DumpPoints` and
+/// `ST_DumpRings`. Load it into [`GeometryDump`](crate::types::GeometryDump).
+pub type GeometryDumpRecord = Record<(Array<Integer>, Geometry)>;
+
+/// Rows `(geom, i, j)` produced by `ST_HexagonGrid` and `ST_SquareGrid`. Load it into
+/// [`GridCell`](crate::types::GridCell).
+pub type GridCellRecord = Record<(Geometry, Integer, Integer)>;
+
+impl KnownCastSqlTypeName<Pg> for Geometry {
+    const SQL_TYPE_NAME: &'static str = "geometry";
+}
+
+impl KnownCastSqlTypeName<Pg> for Geography {
+    const SQL_TYPE_NAME: &'static str = "geography";
+}
+
+impl KnownCastSqlTypeName<Pg> for Box2D {
+    const SQL_TYPE_NAME: &'static str = "box2d";
+}
+
+impl KnownCastSqlTypeName<Pg> for Box3D {
+    const SQL_TYPE_NAME: &'static str = "box3d";
+}
+
+macro_rules! casts_impl {
+    ($($to:ident <- $from:ident),+ $(,)?) => {
+        $(
+            impl FallibleCastsTo<$to> for $from {}
+            impl CastsTo<$to> for $from {}
+        )+
+    };
+}
+
+macro_rules! fallible_casts_impl {
+    ($($to:ident <- $from:ident),+ $(,)?) => {
+        $(
+            impl FallibleCastsTo<$to> for $from {}
+        )+
+    };
+}
+
+// geography rejects SRIDs which are not lon/lat, so the cast can fail at runtime. It has to be
+// listed as infallible all the same: diesel only derives casts between nullable types from
+// `CastsTo`, and the orphan rule forbids implementing them here. `to_geography()` renders the
+// same `CAST` through `fallible_cast`, like the other `GeometryExpressionMethods`.
+casts_impl!(
+    Geography <- Geometry,
+    Geometry <- Geography,
+    Geometry <- Box2D,
+    Geometry <- Box3D,
+    Box2D <- Geometry,
+    Box2D <- Box3D,
+    Box3D <- Geometry,
+    Box3D <- Box2D,
+    Text <- Geometry,
+    Text <- Geography,
+    Text <- Box2D,
+    Text <- Box3D,
+);
+
+// text has to be parsed
+fallible_casts_impl!(
+    Geometry <- Text,
+    Geography <- Text,
+    Box2D <- Text,
+    Box3D <- Text,
+);
@@ -1,6 +1,14 @@
-use std::fmt;
+use std::{fmt, marker::PhantomData};
+
+use diesel::sql_types::Text;
 
 use crate::sql_types::Geometry;
+pub use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+pub use crate::{
+    ewkb::{GeometryType, ReadEwkb, ReadOptions, WriteEwkb, DEFAULT_MAX_DEPTH},
+    points::Dimension,
+};
 
 /// Error which may be returned if point cinstructed without required fields or has some unexpected fields for type.
 /// ```
@@ -97,6 +105,28 @@ pub struct PointZM<const SRID: u32> {
     pub m: f64,
 }
 
+/// Use that structure in `Insertable` or `Queryable` struct if the dimension of the points is only
+/// known at runtime: Z and M are read when present. Writing a container whose non-empty points
+/// have different dimensions fails with [`DimensionMismatch`](crate::error::PostgisError).
+/// ```
+/// #[macro_use] extern crate diesel;
+/// use postgis_diesel::types::{LineString, PointAny};
+/// #[derive(Queryable)]
+/// struct QueryablePointAnyExample {
+///     id: i32,
+///     point: PointAny<4326>,
+///     line: LineString<4326, PointAny<4326>>,
+/// }
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, FromSqlRow, AsExpression, Default)]
+#[diesel(sql_type = Geometry)]
+pub struct PointAny<const SRID: u32> {
+    pub x: f64,
+    pub y: f64,
+    pub z: Option<f64>,
+    pub m: Option<f64>,
+}
+
 pub trait PointT<const SRID: u32>: Default {
     fn new_point(
         x: f64,
@@ -111,6 +141,21 @@ pub trait PointT<const SRID: u32>: Default {
     fn get_z(&self) -> Option<f64>;
     fn get_m(&self) -> Option<f64>;
     fn dimension(&self) -> u32;
+
+    /// Empty point of the dimension of the type, `POINT EMPTY` being encoded with NaN coordinates.
+    /// As NaN is not equal to itself, check it with [`is_empty`](PointT::is_empty).
+    fn empty() -> Self
+    where
+        Self: Sized,
+    {
+        let point = Self::default();
+        let nan = |c: Option<f64>| c.map(|_| f64::NAN);
+        Self::new_point(f64::NAN, f64::NAN, nan(point.get_z()), nan(point.get_m())).unwrap_or(point)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.get_x().is_nan() && self.get_y().is_nan()
+    }
 }
 
 /// Use that structure in `Insertable` or `Queryable` struct if you work with MultiPoint geometry.
@@ -220,10 +265,215 @@ pub struct GeometryCollection<const SRID: u32, T> {
     pub geometries: Vec<GeometryContainer<SRID, T>>,
 }
 
+/// Use that structure to load the `geometry_dump` rows returned by `ST_Dump`, `ST_DumpPoints` or `ST_DumpRings`.
+/// `path` holds the 1-based indexes of the element inside the dumped geometry.
+/// ```
+/// #[macro_use] extern crate diesel;
+/// use postgis_diesel::types::{GeometryDump, Point};
+/// #[derive(Queryable)]
+/// struct QueryableGeometryDumpExample {
+///     id: i32,
+///     part: GeometryDump<4326, Point<4326>>,
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, FromSqlRow)]
+pub struct GeometryDump<const SRID: u32, T> {
+    pub path: Vec<i32>,
+    pub geom: GeometryContainer<SRID, T>,
+}
+
+/// Use that structure in `Queryable` struct if you work with rows of `ST_HexagonGrid` or `ST_SquareGrid`.
+/// `i` and `j` are the column and row of the cell in the grid.
+/// ```
+/// #[macro_use] extern crate diesel;
+/// use postgis_diesel::types::{GridCell, Point};
+/// #[derive(Queryable)]
+/// struct QueryableGridCellExample {
+///     id: i32,
+///     cell: GridCell<4326, Point<4326>>,
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, FromSqlRow)]
+pub struct GridCell<const SRID: u32, T> {
+    pub geom: Polygon<SRID, T>,
+    pub i: i32,
+    pub j: i32,
+}
+
+/// DE-9IM intersection matrix as returned by `ST_Relate`, e.g. `"FF1FF0102"`.
+///
+/// `cells` holds the dimension of the intersections row by row: interior, boundary and exterior
+/// of the first geometry against interior, boundary and exterior of the second one; `None` is an
+/// empty intersection (`F`).
+/// ```
+/// #[macro_use] extern crate diesel;
+/// use postgis_diesel::types::IntersectionMatrix;
+/// #[derive(Queryable)]
+/// struct QueryableRelateExample {
+///     id: i32,
+///     relation: IntersectionMatrix,
+/// }
+///
+/// let matrix: IntersectionMatrix = "0FFFFF212".parse().unwrap();
+/// assert!(matrix.is_within());
+/// assert!(matrix.matches("T*F**F***"));
+/// assert_eq!("0FFFFF212", matrix.to_string());
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, FromSqlRow, AsExpression)]
+#[diesel(sql_type = Text)]
+pub struct IntersectionMatrix {
+    pub cells: [Option<u8>; 9],
+}
+
+/// How the SRID of a geometry read from the database is checked against the SRID of the type it
+/// is loaded into. Plain geometry types are [`Strict`](SridPolicy::Strict), wrap them in
+/// [`Lenient`] to read geometries stored without SRID.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SridPolicy {
+    /// The SRIDs must be equal, geometries without SRID are rejected.
+    Strict,
+    /// Geometries without SRID are read as if they had the SRID of the type.
+    AcceptMissing,
+    /// SRID 0 is unknown: geometries without SRID or with SRID 0 are accepted, and types with
+    /// SRID 0 accept geometries of any SRID.
+    ZeroIsUnknown,
+    /// Any SRID is accepted, for geometries whose SRID is only known at runtime ([`AnySrid`]).
+    Any,
+}
+
+/// Marker selecting the [`SridPolicy`] of [`Lenient`].
+pub trait SridPolicyMarker {
+    const POLICY: SridPolicy;
+}
+
+/// Marker for [`SridPolicy::AcceptMissing`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub struct AcceptMissing;
+
+/// Marker for [`SridPolicy::ZeroIsUnknown`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub struct ZeroIsUnknown;
+
+/// Wraps a geometry type to read it with the SRID policy `P` instead of requiring the exact SRID,
+/// e.g. for legacy tables whose geometries were stored without SRID.
+/// ```
+/// #[macro_use] extern crate diesel;
+/// use postgis_diesel::types::{Lenient, Point, Polygon, ZeroIsUnknown};
+/// #[derive(Queryable)]
+/// struct QueryableLenientExample {
+///     id: i32,
+///     // geometries without SRID are read as SRID 4326
+///     point: Lenient<Point<4326>>,
+///     // geometries without SRID or with SRID 0 are read as SRID 4326
+///     area: Lenient<Polygon<4326, Point<4326>>, ZeroIsUnknown>,
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, FromSqlRow, AsExpression, Default)]
+#[diesel(sql_type = Geometry)]
+pub struct Lenient<G, P = AcceptMissing> {
+    pub geometry: G,
+    pub(crate) policy: PhantomData<P>,
+}
+
+/// Geometry whose SRID is only known at runtime, e.g. for tables mixing SRIDs. `G` is a geometry
+/// type with SRID 0 holding the coordinates, it is encoded with `srid` in place of its own SRID.
+/// Converting into a type with a fixed SRID with `TryFrom` checks the SRID.
+/// ```
+/// #[macro_use] extern crate diesel;
+/// use std::convert::TryFrom;
+/// use postgis_diesel::types::{DynGeometry, DynPoint, Point};
+/// #[derive(Queryable)]
+/// struct QueryableDynExample {
+///     id: i32,
+///     point: DynPoint,
+///     area: DynGeometry,
+/// }
+///
+/// let point = DynPoint::new(Some(4326), Point::new(1.0, 2.0));
+/// assert_eq!(Point::<4326>::new(1.0, 2.0), Point::try_from(point.clone()).unwrap());
+/// assert!(Point::<3857>::try_from(point).is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, FromSqlRow, AsExpression)]
+#[diesel(sql_type = Geometry)]
+pub struct AnySrid<G> {
+    pub srid: Option<u32>,
+    pub geometry: G,
+}
+
+/// Point with a runtime SRID.
+pub type DynPoint = AnySrid<Point<0>>;
+
+/// Geometry of any type with a runtime SRID.
+pub type DynGeometry<P = Point<0>> = AnySrid<GeometryContainer<0, P>>;
+
+/// Wraps a geometry type to read it as 2D, dropping the Z and M coordinates of the stored
+/// geometry like `ST_Force2D` does.
+/// ```
+/// #[macro_use] extern crate diesel;
+/// use postgis_diesel::types::{Force2D, Lenient, LineString, Point};
+/// #[derive(Queryable)]
+/// struct QueryableForce2DExample {
+///     id: i32,
+///     // PointZ data read as Point
+///     line: Force2D<LineString<4326, Point<4326>>>,
+///     // the SRID policy of the wrapped type is kept
+///     legacy: Force2D<Lenient<LineString<4326, Point<4326>>>>,
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, FromSqlRow, AsExpression, Default)]
+#[diesel(sql_type = Geometry)]
+pub struct Force2D<G> {
+    pub geometry: G,
+}
+
+/// Wraps a geometry type to read it with X, Y and Z coordinates, dropping M and setting a missing
+/// Z to 0 like `ST_Force3DZ` does.
+#[derive(Clone, Debug, PartialEq, FromSqlRow, AsExpression, Default)]
+#[diesel(sql_type = Geometry)]
+pub struct Force3DZ<G> {
+    pub geometry: G,
+}
+
+/// Wraps a geometry type to read it with X, Y and M coordinates, dropping Z and setting a missing
+/// M to 0 like `ST_Force3DM` does.
+#[derive(Clone, Debug, PartialEq, FromSqlRow, AsExpression, Default)]
+#[diesel(sql_type = Geometry)]
+pub struct Force3DM<G> {
+    pub geometry: G,
+}
+
+/// Wraps a geometry type to read it with X, Y, Z and M coordinates, setting the missing ones to 0
+/// like `ST_Force4D` does.
+#[derive(Clone, Debug, PartialEq, FromSqlRow, AsExpression, Default)]
+#[diesel(sql_type = Geometry)]
+pub struct Force4D<G> {
+    pub geometry: G,
+}
+
+/// Wraps a geometry type to read at most `DEPTH` nested geometry collections instead of
+/// [`DEFAULT_MAX_DEPTH`], deeper ones failing with
+/// [`NestingTooDeep`](crate::error::PostgisError::NestingTooDeep).
+/// ```
+/// #[macro_use] extern crate diesel;
+/// use postgis_diesel::types::{GeometryCollection, Lenient, MaxDepth, Point};
+/// #[derive(Queryable)]
+/// struct QueryableMaxDepthExample {
+///     id: i32,
+///     collection: MaxDepth<GeometryCollection<4326, Point<4326>>, 4>,
+///     // the wrappers may be combined
+///     legacy: MaxDepth<Lenient<GeometryCollection<4326, Point<4326>>>, 4>,
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, FromSqlRow, AsExpression, Default)]
+#[diesel(sql_type = Geometry)]
+pub struct MaxDepth<G, const DEPTH: u32> {
+    pub geometry: G,
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
-    fn from_iter_test() -> () {
+    fn from_iter_test() {
         use crate::gps::*;
         let gen_points = || vec![Point::new(1., 0.), Point::new(0., 1.)];
         let multi_p: MultiPoint = gen_points().into_iter().collect();
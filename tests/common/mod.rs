@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 use std::{env, sync::Once};
 
 use diesel::{pg::PgConnection, Connection, RunQueryDsl};
@@ -73,8 +75,8 @@ pub struct DistanceSample<const SRID: u32> {
     pub polygon: Polygon<SRID, Point<SRID>>,
 }
 
-impl<const SRID: u32> Into<NewGeometrySample2D<SRID>> for NewGeometrySampleG<SRID, Point<SRID>> {
-    fn into(self) -> NewGeometrySample2D<SRID> {
+impl<const SRID: u32> From<NewGeometrySampleG<SRID, Point<SRID>>> for NewGeometrySample2D<SRID> {
+    fn from(sample: NewGeometrySampleG<SRID, Point<SRID>>) -> Self {
         let NewGeometrySampleG {
             name,
             point,
@@ -84,7 +86,7 @@ impl<const SRID: u32> Into<NewGeometrySample2D<SRID>> for NewGeometrySampleG<SRI
             multiline,
             multipolygon,
             geometrycollection,
-        } = self;
+        } = sample;
         NewGeometrySample2D {
             name,
             point,
@@ -98,8 +100,8 @@ impl<const SRID: u32> Into<NewGeometrySample2D<SRID>> for NewGeometrySampleG<SRI
     }
 }
 
-impl<const SRID: u32> Into<NewGeometrySample3D<SRID>> for NewGeometrySampleG<SRID, PointZ<SRID>> {
-    fn into(self) -> NewGeometrySample3D<SRID> {
+impl<const SRID: u32> From<NewGeometrySampleG<SRID, PointZ<SRID>>> for NewGeometrySample3D<SRID> {
+    fn from(sample: NewGeometrySampleG<SRID, PointZ<SRID>>) -> Self {
         let NewGeometrySampleG {
             name,
             point,
@@ -109,7 +111,7 @@ impl<const SRID: u32> Into<NewGeometrySample3D<SRID>> for NewGeometrySampleG<SRI
             multiline,
             multipolygon,
             geometrycollection,
-        } = self;
+        } = sample;
         NewGeometrySample3D {
             name,
             point,
@@ -214,7 +216,8 @@ fn establish_connection() -> PgConnection {
     let database_url =
         env::var("POSTGIS_DIESEL_DATABASE_URL").expect("POSTGIS_DIESEL_DATABASE_URL not set");
 
-    PgConnection::establish(&database_url).expect(&format!("Error connecting to {}", database_url))
+    PgConnection::establish(&database_url)
+        .unwrap_or_else(|_| panic!("Error connecting to {}", database_url))
 }
 
 pub fn initialize() -> PgConnection {
@@ -250,6 +253,10 @@ pub fn initialize() -> PgConnection {
 )",
         )
         .execute(&mut conn);
+        let _ = diesel::sql_query(
+            "CREATE INDEX distance_samples_point_idx ON distance_samples USING GIST (point)",
+        )
+        .execute(&mut conn);
     });
     conn
 }
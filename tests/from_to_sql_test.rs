@@ -3,11 +3,18 @@ extern crate diesel;
 
 mod common;
 use common::*;
-use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
-use postgis_diesel::types::*;
+use diesel::{ExpressionMethods, IntoSql, QueryDsl, RunQueryDsl};
+use postgis_diesel::{
+    functions::{
+        st_as_ewkb, st_as_ewkb_with_endian, st_as_text, st_geom_from_ewkb, st_is_empty,
+        st_make_point, st_srid,
+    },
+    sql_types::Geometry,
+    types::*,
+};
 
 #[test]
-fn srid_test() -> () {
+fn srid_test() {
     let mut conn = initialize();
     let sample: NewGeometrySample2D<4326> = NewGeometrySampleG::mock("srid").into();
     let point_from_db: GeometrySample<4326, Point<4326>> =
@@ -131,3 +138,288 @@ fn geo_container_test() {
         diesel::delete(geometry_samples::table.filter(geometry_samples::id.eq(point_from_db.id)))
             .execute(&mut conn);
 }
+
+#[test]
+fn point_any_test() {
+    use std::convert::TryFrom;
+
+    let mut conn = initialize();
+    let sample_2d: NewGeometrySample2D<4326> = NewGeometrySampleG::mock("point_any_2d").into();
+    let sample_3d: NewGeometrySample3D<4326> = NewGeometrySampleG::mock("point_any_3d").into();
+
+    let from_db_2d: GeometrySample<4326, PointAny<4326>> =
+        diesel::insert_into(geometry_samples::table)
+            .values(&sample_2d)
+            .get_result(&mut conn)
+            .expect("Error saving 2D geometry sample");
+    let from_db_3d: GeometrySample<4326, PointAny<4326>> =
+        diesel::insert_into(geometry_samples::table)
+            .values(&sample_3d)
+            .get_result(&mut conn)
+            .expect("Error saving 3D geometry sample");
+
+    assert_eq!(PointAny::from(sample_2d.point), from_db_2d.point);
+    assert_eq!(Ok(sample_3d.point), PointZ::try_from(from_db_3d.point));
+    assert!(Point::try_from(from_db_3d.point).is_err());
+    let points: Vec<PointZ<4326>> = from_db_3d
+        .linestring
+        .points
+        .iter()
+        .map(|p| PointZ::try_from(*p).unwrap())
+        .collect();
+    assert_eq!(sample_3d.linestring.points, points);
+    // and back into the database
+    let loaded: MultiPolygon<4326, PointZ<4326>> =
+        diesel::select(from_db_3d.multipolygon.into_sql::<Geometry>())
+            .get_result(&mut conn)
+            .expect("Error writing PointAny geometries");
+    assert_eq!(sample_3d.multipolygon, loaded);
+
+    let _ = diesel::delete(
+        geometry_samples::table.filter(geometry_samples::id.eq_any([from_db_2d.id, from_db_3d.id])),
+    )
+    .execute(&mut conn);
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn nested_ewkb_test() {
+    let mut conn = initialize();
+    let multipoint = MultiPoint::<4326, Point<4326>> {
+        points: vec![Point::new(1.0, 2.0), Point::new(3.0, 4.0)],
+    };
+    let collection = GeometryCollection::<4326, Point<4326>> {
+        geometries: vec![
+            GeometryContainer::Point(Point::new(1.0, 2.0)),
+            GeometryContainer::MultiPoint(MultiPoint {
+                points: vec![Point::new(3.0, 4.0)],
+            }),
+        ],
+    };
+
+    // nested geometries carry neither SRID nor SRID flag
+    let ewkb: Vec<u8> = diesel::select(st_as_ewkb(multipoint.clone().into_sql::<Geometry>()))
+        .get_result(&mut conn)
+        .expect("Error encoding multi point");
+    assert_eq!(
+        "0104000020e6100000020000000101000000000000000000f03f0000000000000040\
+         010100000000000000000008400000000000001040",
+        hex(&ewkb)
+    );
+    let ewkb: Vec<u8> = diesel::select(st_as_ewkb(collection.clone().into_sql::<Geometry>()))
+        .get_result(&mut conn)
+        .expect("Error encoding geometry collection");
+    assert_eq!(
+        "0107000020e6100000020000000101000000000000000000f03f0000000000000040\
+         010400000001000000010100000000000000000008400000000000001040",
+        hex(&ewkb)
+    );
+
+    let loaded: (
+        MultiPoint<4326, Point<4326>>,
+        GeometryCollection<4326, Point<4326>>,
+    ) = diesel::select((
+        multipoint.clone().into_sql::<Geometry>(),
+        collection.clone().into_sql::<Geometry>(),
+    ))
+    .get_result(&mut conn)
+    .expect("Error loading nested geometries");
+    assert_eq!((multipoint, collection), loaded);
+}
+
+#[test]
+fn lenient_srid_test() {
+    let mut conn = initialize();
+
+    // ST_MakePoint returns a geometry without SRID
+    let strict = diesel::select(st_make_point(1.0, 2.0)).get_result::<Point<4326>>(&mut conn);
+    assert!(strict.is_err());
+
+    let lenient: Lenient<Point<4326>> = diesel::select(st_make_point(1.0, 2.0))
+        .get_result(&mut conn)
+        .expect("Error loading point without SRID");
+    assert_eq!(Point::new(1.0, 2.0), lenient.into_inner());
+
+    let lenient: Lenient<Point<4326>, ZeroIsUnknown> = diesel::select(st_make_point(3.0, 4.0))
+        .get_result(&mut conn)
+        .expect("Error loading point without SRID");
+    assert_eq!(Point::new(3.0, 4.0), *lenient);
+
+    // a geometry with another SRID is still rejected
+    let wrong = diesel::select(Point::<3857>::new(1.0, 2.0).into_sql::<Geometry>())
+        .get_result::<Lenient<Point<4326>>>(&mut conn);
+    assert!(wrong.is_err());
+}
+
+#[test]
+fn any_srid_test() {
+    use std::convert::TryFrom;
+
+    let mut conn = initialize();
+    let point = DynPoint::new(Some(3857), Point::new(1.0, 2.0));
+    let line = DynGeometry::new(
+        Some(4326),
+        GeometryContainer::LineString(LineString {
+            points: vec![Point::new(0.0, 0.0), Point::new(1.0, 1.0)],
+        }),
+    );
+
+    let (loaded_point, loaded_line): (DynGeometry, DynGeometry) = diesel::select((
+        point.clone().into_sql::<Geometry>(),
+        line.clone().into_sql::<Geometry>(),
+    ))
+    .get_result(&mut conn)
+    .expect("Error loading geometries with runtime SRID");
+    assert_eq!(Some(3857), loaded_point.srid);
+    assert_eq!(
+        GeometryContainer::Point(point.geometry),
+        loaded_point.geometry
+    );
+    assert_eq!(line, loaded_line);
+
+    let srid: i32 = diesel::select(st_srid(point.clone().into_sql::<Geometry>()))
+        .get_result(&mut conn)
+        .expect("Error getting SRID");
+    assert_eq!(3857, srid);
+
+    assert_eq!(
+        GeometryContainer::<4326, Point<4326>>::LineString(LineString {
+            points: vec![Point::new(0.0, 0.0), Point::new(1.0, 1.0)],
+        }),
+        GeometryContainer::try_from(loaded_line).unwrap()
+    );
+    assert!(Point::<4326>::try_from(point).is_err());
+}
+
+#[test]
+fn force_dimension_test() {
+    let mut conn = initialize();
+    let sample: NewGeometrySample3D<4326> = NewGeometrySampleG::mock("force_dimension").into();
+    let from_db: GeometrySample<4326, PointZ<4326>> = diesel::insert_into(geometry_samples::table)
+        .values(&sample)
+        .get_result(&mut conn)
+        .expect("Error saving geometry sample");
+
+    let line_2d: Force2D<LineString<4326, Point<4326>>> = geometry_samples::table
+        .filter(geometry_samples::id.eq(from_db.id))
+        .select(geometry_samples::linestring)
+        .first(&mut conn)
+        .expect("Error loading line as 2D");
+    let points: Vec<Point<4326>> = sample
+        .linestring
+        .points
+        .iter()
+        .map(|p| Point::new(p.x, p.y))
+        .collect();
+    assert_eq!(points, line_2d.points);
+
+    let line_4d: Force4D<LineString<4326, PointZM<4326>>> = geometry_samples::table
+        .filter(geometry_samples::id.eq(from_db.id))
+        .select(geometry_samples::linestring)
+        .first(&mut conn)
+        .expect("Error loading line as 4D");
+    let points: Vec<PointZM<4326>> = sample
+        .linestring
+        .points
+        .iter()
+        .map(|p| PointZM::new(p.x, p.y, p.z, 0.0))
+        .collect();
+    assert_eq!(points, line_4d.points);
+
+    let _ = diesel::delete(geometry_samples::table.filter(geometry_samples::id.eq(from_db.id)))
+        .execute(&mut conn);
+}
+
+#[test]
+fn empty_geometries_test() {
+    let mut conn = initialize();
+    let empty_point = Point::<4326>::empty();
+    let empty_line = LineString::<4326, PointZ<4326>> { points: vec![] };
+
+    let (text, is_empty): (String, bool) = diesel::select((
+        st_as_text(empty_point.into_sql::<Geometry>()),
+        st_is_empty(empty_point.into_sql::<Geometry>()),
+    ))
+    .get_result(&mut conn)
+    .expect("Error encoding empty point");
+    assert_eq!("POINT EMPTY", text);
+    assert!(is_empty);
+    let text: String = diesel::select(st_as_text(empty_line.clone().into_sql::<Geometry>()))
+        .get_result(&mut conn)
+        .expect("Error encoding empty line");
+    assert_eq!("LINESTRING Z EMPTY", text);
+
+    let (point, line): (Point<4326>, LineString<4326, PointZ<4326>>) = diesel::select((
+        empty_point.into_sql::<Geometry>(),
+        empty_line.clone().into_sql::<Geometry>(),
+    ))
+    .get_result(&mut conn)
+    .expect("Error loading empty geometries");
+    assert!(point.is_empty());
+    assert_eq!(empty_line, line);
+}
+
+#[test]
+fn nested_collections_test() {
+    let mut conn = initialize();
+    let collection = GeometryCollection::<4326, Point<4326>> {
+        geometries: vec![GeometryContainer::GeometryCollection(GeometryCollection {
+            geometries: vec![
+                GeometryContainer::GeometryCollection(GeometryCollection {
+                    geometries: vec![GeometryContainer::Point(Point::new(1.0, 2.0))],
+                }),
+                GeometryContainer::Point(Point::new(3.0, 4.0)),
+            ],
+        })],
+    };
+
+    let text: String = diesel::select(st_as_text(collection.clone().into_sql::<Geometry>()))
+        .get_result(&mut conn)
+        .expect("Error encoding nested collections");
+    assert_eq!(
+        "GEOMETRYCOLLECTION(GEOMETRYCOLLECTION(GEOMETRYCOLLECTION(POINT(1 2)),POINT(3 4)))",
+        text
+    );
+    let loaded: GeometryCollection<4326, Point<4326>> =
+        diesel::select(collection.clone().into_sql::<Geometry>())
+            .get_result(&mut conn)
+            .expect("Error loading nested collections");
+    assert_eq!(collection, loaded);
+}
+
+#[test]
+fn byte_order_test() {
+    let mut conn = initialize();
+    let polygon = Polygon::<4326, PointZ<4326>> {
+        rings: vec![LineString {
+            points: vec![
+                PointZ::new(0.0, 0.0, 1.0),
+                PointZ::new(1.0, 0.0, 2.0),
+                PointZ::new(0.0, 1.0, 3.0),
+                PointZ::new(0.0, 0.0, 1.0),
+            ],
+        }],
+    };
+    let (mut ndr, mut xdr) = (Vec::new(), Vec::new());
+    polygon
+        .write_ewkb::<LittleEndian, _>(Some(4326), &mut ndr)
+        .unwrap();
+    polygon
+        .write_ewkb::<BigEndian, _>(Some(4326), &mut xdr)
+        .unwrap();
+
+    // PostGIS reads both byte orders and writes the same bytes
+    for (bytes, endian) in [(ndr, "NDR"), (xdr, "XDR")] {
+        let (loaded, ewkb): (Polygon<4326, PointZ<4326>>, Vec<u8>) = diesel::select((
+            st_geom_from_ewkb(bytes.clone()),
+            st_as_ewkb_with_endian(st_geom_from_ewkb(bytes.clone()), endian),
+        ))
+        .get_result(&mut conn)
+        .expect("Error decoding EWKB");
+        assert_eq!(polygon, loaded);
+        assert_eq!(bytes, ewkb);
+    }
+}
@@ -0,0 +1,1055 @@
+#[macro_use]
+extern crate diesel;
+
+mod common;
+use common::*;
+use diesel::{
+    debug_query, pg::Pg, sql_types::Nullable, ExpressionMethods, IntoSql,
+    NullableExpressionMethods, QueryDsl, RunQueryDsl, WindowExpressionMethods,
+};
+use postgis_diesel::{
+    expression_methods::GeometryExpressionMethods,
+    functions::*,
+    gps, grid,
+    sql_types::{Box2D, Geometry},
+    types::*,
+};
+
+#[derive(Queryable, Debug, PartialEq)]
+struct PolygonMeasurements {
+    name: String,
+    polygon: gps::Polygon,
+    area: f64,
+    perimeter: f64,
+}
+
+#[test]
+fn measurement_functions_sql_test() {
+    let query = distance_samples::table.select((
+        st_area(distance_samples::polygon),
+        st_perimeter_geography(
+            diesel::dsl::sql::<postgis_diesel::sql_types::Geography>("polygon::geography"),
+            true,
+        ),
+    ));
+    assert_eq!(
+        debug_query::<Pg, _>(&query).to_string(),
+        "SELECT ST_Area(\"distance_samples\".\"polygon\"), ST_Perimeter(polygon::geography, $1) \
+         FROM \"distance_samples\" -- binds: [true]"
+    );
+}
+
+#[test]
+fn measurement_functions_test() {
+    let mut conn = initialize();
+    let mut polygon = gps::Polygon::new();
+    polygon.add_points(&[
+        gps::Point::new(0.0, 0.0),
+        gps::Point::new(2.0, 0.0),
+        gps::Point::new(2.0, 3.0),
+        gps::Point::new(0.0, 3.0),
+        gps::Point::new(0.0, 0.0),
+    ]);
+    let sample = NewDistanceSample {
+        name: String::from("measurement_functions_test"),
+        point: gps::Point::new(0.0, 0.0),
+        polygon: polygon.clone(),
+    };
+    diesel::insert_into(distance_samples::table)
+        .values(&sample)
+        .execute(&mut conn)
+        .expect("Error saving distance sample");
+
+    let measured: PolygonMeasurements = distance_samples::table
+        .filter(distance_samples::name.eq("measurement_functions_test"))
+        .select((
+            distance_samples::name,
+            distance_samples::polygon,
+            st_area(distance_samples::polygon),
+            st_perimeter(distance_samples::polygon),
+        ))
+        .get_result(&mut conn)
+        .expect("Error measuring polygon");
+    assert_eq!(
+        PolygonMeasurements {
+            name: String::from("measurement_functions_test"),
+            polygon,
+            area: 6.0,
+            perimeter: 10.0,
+        },
+        measured
+    );
+
+    let line: LineString<4326, Point<4326>> = new_line(vec![(0.0, 0.0), (3.0, 4.0)]);
+    let length: f64 = diesel::select(st_length(line.into_sql::<Geometry>()))
+        .get_result(&mut conn)
+        .expect("Error getting length");
+    assert_eq!(5.0, length);
+
+    let distance: f64 = distance_samples::table
+        .filter(distance_samples::name.eq("measurement_functions_test"))
+        .select(st_distance(
+            distance_samples::point,
+            new_point::<4326>(3.0, 4.0).into_sql::<Geometry>(),
+        ))
+        .get_result(&mut conn)
+        .expect("Error getting distance");
+    assert_eq!(5.0, distance);
+
+    let distance_3d: f64 = diesel::select(st_3d_distance(
+        PointZ::<4326>::new(0.0, 0.0, 0.0).into_sql::<Geometry>(),
+        PointZ::<4326>::new(2.0, 3.0, 6.0).into_sql::<Geometry>(),
+    ))
+    .get_result(&mut conn)
+    .expect("Error getting 3D distance");
+    assert_eq!(7.0, distance_3d);
+
+    let hausdorff: f64 = diesel::select(st_hausdorff_distance(
+        new_line::<4326>(vec![(0.0, 0.0), (2.0, 0.0)]).into_sql::<Geometry>(),
+        new_line::<4326>(vec![(0.0, 1.0), (2.0, 1.0)]).into_sql::<Geometry>(),
+    ))
+    .get_result(&mut conn)
+    .expect("Error getting Hausdorff distance");
+    assert_eq!(1.0, hausdorff);
+
+    let _ = diesel::delete(
+        distance_samples::table.filter(distance_samples::name.eq("measurement_functions_test")),
+    )
+    .execute(&mut conn);
+}
+
+#[test]
+fn constructor_functions_sql_test() {
+    let query = diesel::insert_into(distance_samples::table).values((
+        distance_samples::name.eq("constructor"),
+        distance_samples::point.eq(st_set_srid(st_make_point(3.0, 4.0), 4326)),
+        distance_samples::polygon.eq(st_make_envelope(0.0, 0.0, 2.0, 3.0, 4326)),
+    ));
+    assert_eq!(
+        debug_query::<Pg, _>(&query).to_string(),
+        "INSERT INTO \"distance_samples\" (\"name\", \"point\", \"polygon\") \
+         VALUES ($1, ST_SetSRID(ST_MakePoint($2, $3), $4), ST_MakeEnvelope($5, $6, $7, $8, $9)) \
+         -- binds: [\"constructor\", 3.0, 4.0, 4326, 0.0, 0.0, 2.0, 3.0, 4326]"
+    );
+}
+
+#[test]
+fn constructor_functions_test() {
+    let mut conn = initialize();
+    let sample: DistanceSample<4326> = diesel::insert_into(distance_samples::table)
+        .values((
+            distance_samples::name.eq("constructor_functions_test"),
+            distance_samples::point.eq(st_set_srid(st_make_point(3.0, 4.0), 4326)),
+            distance_samples::polygon.eq(st_make_envelope(0.0, 0.0, 2.0, 3.0, 4326)),
+        ))
+        .get_result(&mut conn)
+        .expect("Error saving distance sample");
+    assert_eq!(Point::new(3.0, 4.0), sample.point);
+    let mut envelope = Polygon::new();
+    envelope.add_points(&[
+        Point::new(0.0, 0.0),
+        Point::new(0.0, 3.0),
+        Point::new(2.0, 3.0),
+        Point::new(2.0, 0.0),
+        Point::new(0.0, 0.0),
+    ]);
+    assert_eq!(envelope, sample.polygon);
+
+    let line: LineString<4326, Point<4326>> = diesel::select(st_reverse(st_set_srid(
+        st_make_line(st_make_point(0.0, 0.0), st_make_point(1.0, 2.0)),
+        4326,
+    )))
+    .get_result(&mut conn)
+    .expect("Error making line");
+    assert_eq!(new_line(vec![(1.0, 2.0), (0.0, 0.0)]), line);
+
+    let line_z: LineString<4326, PointZ<4326>> =
+        diesel::select(st_force_3d(st_make_line_array(vec![
+            new_point::<4326>(0.0, 0.0),
+            new_point(1.0, 2.0),
+        ])))
+        .get_result(&mut conn)
+        .expect("Error making 3D line");
+    assert_eq!(new_line_z(vec![(0.0, 0.0, 0.0), (1.0, 2.0, 0.0)]), line_z);
+
+    let polygon: Polygon<4326, Point<4326>> =
+        diesel::select(st_force_2d(st_make_polygon(st_set_srid(
+            st_make_line_array(vec![
+                PointZ::<0>::new(0.0, 0.0, 1.0),
+                PointZ::new(0.0, 3.0, 1.0),
+                PointZ::new(2.0, 3.0, 1.0),
+                PointZ::new(2.0, 0.0, 1.0),
+                PointZ::new(0.0, 0.0, 1.0),
+            ]),
+            4326,
+        ))))
+        .get_result(&mut conn)
+        .expect("Error making polygon");
+    assert_eq!(envelope, polygon);
+
+    let point: PointZM<4326> =
+        diesel::select(st_set_srid(st_make_point_zm(1.0, 2.0, 3.0, 4.0), 4326))
+            .get_result(&mut conn)
+            .expect("Error making point");
+    assert_eq!(PointZM::new(1.0, 2.0, 3.0, 4.0), point);
+
+    let _ = diesel::delete(
+        distance_samples::table.filter(distance_samples::name.eq("constructor_functions_test")),
+    )
+    .execute(&mut conn);
+}
+
+#[test]
+fn transform_sql_test() {
+    let query = distance_samples::table.select(transform::<3857, _>(distance_samples::point));
+    assert_eq!(
+        debug_query::<Pg, _>(&query).to_string(),
+        "SELECT ST_Transform(\"distance_samples\".\"point\", 3857) FROM \"distance_samples\" \
+         -- binds: []"
+    );
+}
+
+#[test]
+fn transform_test() {
+    let mut conn = initialize();
+    let sample = NewDistanceSample {
+        name: String::from("transform_test"),
+        point: gps::Point::new(0.0, 0.0),
+        polygon: gps::Polygon::new(),
+    };
+    diesel::insert_into(distance_samples::table)
+        .values(&sample)
+        .execute(&mut conn)
+        .expect("Error saving distance sample");
+
+    macro_rules! get_transformed {
+        ($srid:expr) => {
+            distance_samples::table
+                .filter(distance_samples::name.eq("transform_test"))
+                .select(transform::<3857, _>(distance_samples::point))
+                .get_result::<Point<$srid>>(&mut conn)
+        };
+    }
+
+    let mercator = get_transformed!(3857).expect("Error transforming point");
+    assert!(mercator.x.abs() < 1e-6);
+    assert!(mercator.y.abs() < 1e-6);
+
+    let wrong_srid = get_transformed!(4326);
+    assert_eq!(
+        wrong_srid.unwrap_err().to_string(),
+        "Wrong SRID in database: Some(3857), Expected: 4326"
+    );
+
+    let _ =
+        diesel::delete(distance_samples::table.filter(distance_samples::name.eq("transform_test")))
+            .execute(&mut conn);
+}
+
+#[test]
+fn accessor_functions_sql_test() {
+    let query = geometry_samples::table
+        .filter(st_geometry_type(geometry_samples::polygon).eq("ST_Polygon"))
+        .filter(st_npoints(geometry_samples::polygon).gt(1000))
+        .select(geometry_samples::id);
+    assert_eq!(
+        debug_query::<Pg, _>(&query).to_string(),
+        "SELECT \"geometry_samples\".\"id\" FROM \"geometry_samples\" \
+         WHERE ((ST_GeometryType(\"geometry_samples\".\"polygon\") = $1) \
+         AND (ST_NPoints(\"geometry_samples\".\"polygon\") > $2)) -- binds: [\"ST_Polygon\", 1000]"
+    );
+}
+
+#[test]
+fn accessor_functions_test() {
+    let mut conn = initialize();
+    let sample: NewGeometrySample3D<4326> =
+        NewGeometrySampleG::mock("accessor_functions_test").into();
+    let sample_from_db: GeometrySample<4326, PointZ<4326>> =
+        diesel::insert_into(geometry_samples::table)
+            .values(&sample)
+            .get_result(&mut conn)
+            .expect("Error saving geometry sample");
+
+    let found: (
+        f64,
+        f64,
+        Option<f64>,
+        Option<f64>,
+        i32,
+        i32,
+        String,
+        bool,
+        bool,
+    ) = geometry_samples::table
+        .filter(geometry_samples::id.eq(sample_from_db.id))
+        .filter(st_geometry_type(geometry_samples::polygon).eq("ST_Polygon"))
+        .filter(st_npoints(geometry_samples::linestring).gt(1))
+        .select((
+            st_x(geometry_samples::point),
+            st_y(geometry_samples::point),
+            st_z(geometry_samples::point),
+            st_m(geometry_samples::point),
+            st_srid(geometry_samples::point),
+            st_npoints(geometry_samples::multipolygon),
+            st_geometry_type(geometry_samples::multiline),
+            st_is_empty(geometry_samples::geometrycollection),
+            st_is_valid(geometry_samples::linestring),
+        ))
+        .get_result(&mut conn)
+        .expect("Error getting geometry properties");
+    assert_eq!(
+        (
+            1.0,
+            3.0,
+            Some(1.0),
+            None,
+            4326,
+            10,
+            String::from("ST_MultiLineString"),
+            false,
+            true
+        ),
+        found
+    );
+
+    let not_found = geometry_samples::table
+        .filter(geometry_samples::id.eq(sample_from_db.id))
+        .filter(st_npoints(geometry_samples::linestring).gt(1000))
+        .select(geometry_samples::id)
+        .get_result::<i32>(&mut conn);
+    assert_eq!(Err(diesel::result::Error::NotFound), not_found);
+
+    let _ =
+        diesel::delete(geometry_samples::table.filter(geometry_samples::id.eq(sample_from_db.id)))
+            .execute(&mut conn);
+}
+
+#[test]
+fn processing_functions_sql_test() {
+    let query = distance_samples::table.select(st_buffer(distance_samples::point, 10.0));
+    assert_eq!(
+        debug_query::<Pg, _>(&query).to_string(),
+        "SELECT ST_Buffer(\"distance_samples\".\"point\", $1) FROM \"distance_samples\" \
+         -- binds: [10.0]"
+    );
+}
+
+#[test]
+fn processing_functions_test() {
+    let mut conn = initialize();
+
+    macro_rules! get {
+        ($e:expr) => {
+            diesel::select($e)
+                .get_result::<GeometryContainer<4326, Point<4326>>>(&mut conn)
+                .expect("Error processing geometry")
+        };
+    }
+
+    let point = || new_point::<4326>(1.0, 1.0).into_sql::<Geometry>();
+    let square = |x: f64| st_make_envelope(x, 0.0, x + 2.0, 2.0, 4326);
+
+    let buffer = get!(st_buffer(point(), 1.0));
+    assert!(matches!(buffer, GeometryContainer::Polygon(_)));
+    let buffer = get!(st_buffer_with_params(point(), 1.0, "quad_segs=2"));
+    match buffer {
+        GeometryContainer::Polygon(polygon) => assert_eq!(9, polygon.rings[0].points.len()),
+        other => panic!("unexpected buffer {:?}", other),
+    }
+
+    let intersection = get!(st_intersection(square(0.0), square(1.0)));
+    assert!(matches!(intersection, GeometryContainer::Polygon(_)));
+    let union = get!(st_union(square(0.0), square(3.0)));
+    assert!(matches!(union, GeometryContainer::MultiPolygon(_)));
+    let difference = get!(st_difference(square(0.0), square(0.0)));
+    assert!(matches!(difference, GeometryContainer::Polygon(p) if p.rings.is_empty()));
+
+    let centroid = get!(st_centroid(square(0.0)));
+    assert_eq!(GeometryContainer::Point(new_point(1.0, 1.0)), centroid);
+
+    let line = new_line::<4326>(vec![(0.0, 0.0), (1.0, 0.1), (2.0, 0.0)]);
+    let simplified = get!(st_simplify(line.into_sql::<Geometry>(), 0.5));
+    assert_eq!(
+        GeometryContainer::LineString(new_line(vec![(0.0, 0.0), (2.0, 0.0)])),
+        simplified
+    );
+
+    let multipoint: MultiPoint<4326, Point<4326>> = vec![
+        new_point(0.0, 0.0),
+        new_point(2.0, 0.0),
+        new_point(1.0, 1.0),
+        new_point(1.0, 0.5),
+    ]
+    .into_iter()
+    .collect();
+    let hull = get!(st_convex_hull(multipoint.into_sql::<Geometry>()));
+    match hull {
+        GeometryContainer::Polygon(polygon) => assert_eq!(4, polygon.rings[0].points.len()),
+        other => panic!("unexpected hull {:?}", other),
+    }
+}
+
+#[test]
+fn aggregate_functions_sql_test() {
+    let query = distance_samples::table
+        .group_by(distance_samples::name)
+        .select((
+            distance_samples::name,
+            st_union_agg(distance_samples::polygon),
+            st_extent(distance_samples::polygon).cast::<Nullable<Geometry>>(),
+        ));
+    assert_eq!(
+        debug_query::<Pg, _>(&query).to_string(),
+        "SELECT \"distance_samples\".\"name\", ST_Union(\"distance_samples\".\"polygon\"), \
+         CAST(ST_Extent(\"distance_samples\".\"polygon\") AS geometry) \
+         FROM \"distance_samples\" GROUP BY \"distance_samples\".\"name\" -- binds: []"
+    );
+}
+
+#[test]
+fn aggregate_functions_test() {
+    let mut conn = initialize();
+    let square = |x: f64| {
+        let mut polygon = gps::Polygon::new();
+        polygon.add_points(&[
+            gps::Point::new(x, 0.0),
+            gps::Point::new(x, 1.0),
+            gps::Point::new(x + 1.0, 1.0),
+            gps::Point::new(x + 1.0, 0.0),
+            gps::Point::new(x, 0.0),
+        ]);
+        polygon
+    };
+    let samples: Vec<_> = [0.0, 2.0]
+        .iter()
+        .map(|x| NewDistanceSample {
+            name: String::from("aggregate_functions_test"),
+            point: gps::Point::new(*x, *x),
+            polygon: square(*x),
+        })
+        .collect();
+    diesel::insert_into(distance_samples::table)
+        .values(&samples)
+        .execute(&mut conn)
+        .expect("Error saving distance samples");
+
+    #[allow(clippy::type_complexity)]
+    let (name, union, collected, line, extent, extent_3d): (
+        String,
+        Option<gps::MultiPolygon>,
+        Option<gps::GeometryContainer>,
+        Option<gps::LineString>,
+        gps::Polygon,
+        gps::GeometryContainer,
+    ) = distance_samples::table
+        .filter(distance_samples::name.eq("aggregate_functions_test"))
+        .group_by(distance_samples::name)
+        .select((
+            distance_samples::name,
+            st_union_agg(distance_samples::polygon),
+            st_collect_agg(distance_samples::point),
+            st_make_line_agg(distance_samples::point),
+            st_set_srid(
+                st_extent(distance_samples::polygon)
+                    .cast::<Nullable<Geometry>>()
+                    .assume_not_null(),
+                4326,
+            ),
+            st_force_2d(st_set_srid(
+                st_3d_extent(distance_samples::polygon)
+                    .cast::<Nullable<Geometry>>()
+                    .assume_not_null(),
+                4326,
+            )),
+        ))
+        .get_result(&mut conn)
+        .expect("Error aggregating samples");
+
+    assert_eq!("aggregate_functions_test", name);
+    assert_eq!(2, union.expect("union is null").polygons.len());
+    match collected.expect("collection is null") {
+        GeometryContainer::MultiPoint(multipoint) => assert_eq!(2, multipoint.points.len()),
+        other => panic!("unexpected collection {:?}", other),
+    }
+    assert_eq!(2, line.expect("line is null").points.len());
+    let mut expected_extent = gps::Polygon::new();
+    expected_extent.add_points(&[
+        gps::Point::new(0.0, 0.0),
+        gps::Point::new(0.0, 1.0),
+        gps::Point::new(3.0, 1.0),
+        gps::Point::new(3.0, 0.0),
+        gps::Point::new(0.0, 0.0),
+    ]);
+    assert_eq!(expected_extent, extent);
+    assert_eq!(GeometryContainer::Polygon(expected_extent), extent_3d);
+
+    let _ = diesel::delete(
+        distance_samples::table.filter(distance_samples::name.eq("aggregate_functions_test")),
+    )
+    .execute(&mut conn);
+}
+
+#[test]
+fn output_functions_sql_test() {
+    let query = distance_samples::table.select((
+        geojson_to_jsonb(st_as_geojson_with_options(distance_samples::point, 6, 2)),
+        st_as_mvt_geom(
+            transform::<3857, _>(distance_samples::polygon),
+            st_tile_envelope(1, 0, 0).cast::<Box2D>(),
+        ),
+    ));
+    assert_eq!(
+        debug_query::<Pg, _>(&query).to_string(),
+        "SELECT CAST(ST_AsGeoJSON(\"distance_samples\".\"point\", $1, $2) AS jsonb), \
+         ST_AsMVTGeom(ST_Transform(\"distance_samples\".\"polygon\", 3857), \
+         CAST(ST_TileEnvelope($3, $4, $5) AS box2d)) \
+         FROM \"distance_samples\" -- binds: [6, 2, 1, 0, 0]"
+    );
+}
+
+#[test]
+fn output_functions_test() {
+    let mut conn = initialize();
+    let point = || new_point::<4326>(1.0, 2.0).into_sql::<Geometry>();
+
+    #[allow(clippy::type_complexity)]
+    let (text, text_digits, ewkt, geojson, geojson_digits, geojson_crs, jsonb): (
+        String,
+        String,
+        String,
+        String,
+        String,
+        String,
+        String,
+    ) = diesel::select((
+        st_as_text(point()),
+        st_as_text_with_digits(new_point::<4326>(1.123, 2.0).into_sql::<Geometry>(), 1),
+        st_as_ewkt(point()),
+        st_as_geojson(point()),
+        st_as_geojson_with_digits(new_point::<4326>(1.123, 2.0).into_sql::<Geometry>(), 1),
+        st_as_geojson_with_options(point(), 9, 2),
+        geojson_to_jsonb(st_as_geojson(point())).cast::<diesel::sql_types::Text>(),
+    ))
+    .get_result(&mut conn)
+    .expect("Error formatting geometry");
+    assert_eq!("POINT(1 2)", text);
+    assert_eq!("POINT(1.1 2)", text_digits);
+    assert_eq!("SRID=4326;POINT(1 2)", ewkt);
+    assert_eq!(r#"{"type":"Point","coordinates":[1,2]}"#, geojson);
+    assert_eq!(r#"{"type":"Point","coordinates":[1.1,2]}"#, geojson_digits);
+    assert_eq!(
+        r#"{"type":"Point","crs":{"type":"name","properties":{"name":"EPSG:4326"}},"coordinates":[1,2]}"#,
+        geojson_crs
+    );
+    assert_eq!(r#"{"type": "Point", "coordinates": [1, 2]}"#, jsonb);
+
+    let (wkb, wkb_xdr, twkb): (Vec<u8>, Vec<u8>, Vec<u8>) = diesel::select((
+        st_as_binary(point()),
+        st_as_binary_with_endian(point(), "XDR"),
+        st_as_twkb(point(), 0),
+    ))
+    .get_result(&mut conn)
+    .expect("Error encoding geometry");
+    assert_eq!(
+        vec![1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 240, 63, 0, 0, 0, 0, 0, 0, 0, 64],
+        wkb
+    );
+    assert_eq!(
+        vec![0, 0, 0, 0, 1, 63, 240, 0, 0, 0, 0, 0, 0, 64, 0, 0, 0, 0, 0, 0, 0],
+        wkb_xdr
+    );
+    assert_eq!(vec![1, 0, 2, 4], twkb);
+
+    let tile_geometry_type: String = diesel::select(st_geometry_type(
+        st_as_mvt_geom_with_options(
+            transform::<3857, _>(point()),
+            st_tile_envelope(0, 0, 0).cast::<Box2D>(),
+            4096,
+            0,
+            true,
+        )
+        .assume_not_null(),
+    ))
+    .get_result(&mut conn)
+    .expect("Error getting MVT geometry");
+    assert_eq!("ST_Point", tile_geometry_type);
+}
+
+#[test]
+fn set_returning_functions_sql_test() {
+    let query = geometry_samples::table.select(st_dump(geometry_samples::multipolygon));
+    assert_eq!(
+        debug_query::<Pg, _>(&query).to_string(),
+        "SELECT ST_Dump(\"geometry_samples\".\"multipolygon\") FROM \"geometry_samples\" \
+         -- binds: []"
+    );
+}
+
+#[test]
+fn set_returning_functions_test() {
+    let mut conn = initialize();
+    let sample: NewGeometrySample2D<4326> =
+        NewGeometrySampleG::mock("set_returning_functions_test").into();
+    let sample_from_db: GeometrySample<4326, Point<4326>> =
+        diesel::insert_into(geometry_samples::table)
+            .values(&sample)
+            .get_result(&mut conn)
+            .expect("Error saving geometry sample");
+    let by_id = geometry_samples::table.filter(geometry_samples::id.eq(sample_from_db.id));
+
+    let parts: Vec<GeometryDump<4326, Point<4326>>> = by_id
+        .select(st_dump(geometry_samples::multipolygon))
+        .load(&mut conn)
+        .expect("Error dumping multipolygon");
+    let expected: Vec<_> = sample
+        .multipolygon
+        .polygons
+        .iter()
+        .enumerate()
+        .map(|(i, polygon)| GeometryDump {
+            path: vec![i as i32 + 1],
+            geom: GeometryContainer::Polygon(polygon.clone()),
+        })
+        .collect();
+    assert_eq!(expected, parts);
+
+    let points: Vec<GeometryDump<4326, Point<4326>>> = by_id
+        .select(st_dump_points(geometry_samples::linestring))
+        .load(&mut conn)
+        .expect("Error dumping points");
+    let expected: Vec<_> = sample
+        .linestring
+        .points
+        .iter()
+        .enumerate()
+        .map(|(i, point)| GeometryDump {
+            path: vec![i as i32 + 1],
+            geom: GeometryContainer::Point(*point),
+        })
+        .collect();
+    assert_eq!(expected, points);
+
+    let rings: Vec<GeometryDump<4326, Point<4326>>> = by_id
+        .select(st_dump_rings(geometry_samples::polygon))
+        .load(&mut conn)
+        .expect("Error dumping rings");
+    assert_eq!(
+        vec![GeometryDump {
+            path: vec![0],
+            geom: GeometryContainer::Polygon(sample.polygon.clone()),
+        }],
+        rings
+    );
+
+    let subdivided: Vec<GeometryContainer<4326, Point<4326>>> = diesel::select(st_subdivide(
+        st_make_envelope(0.0, 0.0, 10.0, 10.0, 4326),
+        5,
+    ))
+    .load(&mut conn)
+    .expect("Error subdividing polygon");
+    assert!(!subdivided.is_empty());
+    for part in subdivided {
+        match part {
+            GeometryContainer::Polygon(polygon) => assert!(polygon.rings[0].points.len() <= 5),
+            other => panic!("unexpected part {:?}", other),
+        }
+    }
+
+    let _ = diesel::delete(by_id).execute(&mut conn);
+}
+
+#[test]
+fn clustering_functions_sql_test() {
+    let query = distance_samples::table.select((
+        distance_samples::name,
+        st_cluster_dbscan(distance_samples::point, 1.0, 2).over(),
+        st_cluster_kmeans(distance_samples::point, 2).partition_by(distance_samples::name),
+    ));
+    assert_eq!(
+        debug_query::<Pg, _>(&query).to_string(),
+        "SELECT \"distance_samples\".\"name\", \
+         ST_ClusterDBSCAN(\"distance_samples\".\"point\", $1, $2) OVER (), \
+         ST_ClusterKMeans(\"distance_samples\".\"point\", $3) \
+         OVER ( PARTITION BY \"distance_samples\".\"name\") FROM \"distance_samples\" \
+         -- binds: [1.0, 2, 2]"
+    );
+}
+
+#[test]
+fn clustering_functions_test() {
+    let mut conn = initialize();
+    let samples: Vec<_> = [(0.0, 0.0), (0.5, 0.0), (10.0, 10.0)]
+        .iter()
+        .map(|(x, y)| NewDistanceSample {
+            name: String::from("clustering_functions_test"),
+            point: gps::Point::new(*x, *y),
+            polygon: gps::Polygon::new(),
+        })
+        .collect();
+    diesel::insert_into(distance_samples::table)
+        .values(&samples)
+        .execute(&mut conn)
+        .expect("Error saving distance samples");
+    let by_name =
+        distance_samples::table.filter(distance_samples::name.eq("clustering_functions_test"));
+
+    #[allow(clippy::type_complexity)]
+    let clusters: Vec<(gps::Point, Option<i32>, Option<i32>, Option<i32>)> = by_name
+        .select((
+            distance_samples::point,
+            st_cluster_dbscan(distance_samples::point, 1.0, 2).over(),
+            st_cluster_kmeans(distance_samples::point, 2).over(),
+            st_cluster_within_win(distance_samples::point, 1.0).over(),
+        ))
+        .order_by(distance_samples::id)
+        .load(&mut conn)
+        .expect("Error clustering samples");
+    assert_eq!(3, clusters.len());
+    assert_eq!(Some(0), clusters[0].1);
+    assert_eq!(Some(0), clusters[1].1);
+    assert_eq!(None, clusters[2].1);
+    assert_eq!(clusters[0].2, clusters[1].2);
+    assert_ne!(clusters[0].2, clusters[2].2);
+    assert_eq!(clusters[0].3, clusters[1].3);
+    assert_ne!(clusters[0].3, clusters[2].3);
+
+    let collections: Option<Vec<gps::GeometryContainer>> = by_name
+        .select(st_cluster_within(distance_samples::point, 1.0))
+        .get_result(&mut conn)
+        .expect("Error clustering samples");
+    assert_eq!(2, collections.expect("no clusters").len());
+
+    let _ = diesel::delete(by_name).execute(&mut conn);
+}
+
+fn assert_cells_eq(
+    expected: &[GridCell<4326, Point<4326>>],
+    actual: &[GridCell<4326, Point<4326>>],
+) {
+    assert_eq!(expected.len(), actual.len());
+    for (e, a) in expected.iter().zip(actual) {
+        assert_eq!((e.i, e.j), (a.i, a.j));
+        let (e_points, a_points) = (&e.geom.rings[0].points, &a.geom.rings[0].points);
+        assert_eq!(e_points.len(), a_points.len());
+        for (ep, ap) in e_points.iter().zip(a_points) {
+            assert!((ep.x - ap.x).abs() < 1e-9 && (ep.y - ap.y).abs() < 1e-9);
+        }
+    }
+}
+
+#[test]
+fn grid_functions_sql_test() {
+    let query = diesel::select(st_hexagon_grid(
+        1.0,
+        st_make_envelope(0.1, 0.1, 2.0, 1.0, 4326),
+    ));
+    assert_eq!(
+        debug_query::<Pg, _>(&query).to_string(),
+        "SELECT ST_HexagonGrid($1, ST_MakeEnvelope($2, $3, $4, $5, $6)) \
+         -- binds: [1.0, 0.1, 0.1, 2.0, 1.0, 4326]"
+    );
+    let query = diesel::select(st_square_with_origin(
+        1.0,
+        2,
+        3,
+        Point::<4326>::new(0.5, 0.5),
+    ));
+    assert_eq!(
+        debug_query::<Pg, _>(&query).to_string(),
+        "SELECT ST_Square($1, $2, $3, $4) -- binds: [1.0, 2, 3, Point { x: 0.5, y: 0.5 }]"
+    );
+}
+
+#[test]
+fn grid_functions_test() {
+    let mut conn = initialize();
+    let (min, max) = (Point::<4326>::new(0.3, 0.2), Point::<4326>::new(4.1, 2.9));
+
+    let mut hexagons: Vec<GridCell<4326, Point<4326>>> = diesel::select(st_hexagon_grid(
+        1.0,
+        st_make_envelope(0.3, 0.2, 4.1, 2.9, 4326),
+    ))
+    .load(&mut conn)
+    .expect("Error generating hexagon grid");
+    hexagons.sort_by_key(|c| (c.i, c.j));
+    assert_cells_eq(&grid::hexagon_grid(1.0, min, max), &hexagons);
+
+    let mut squares: Vec<GridCell<4326, Point<4326>>> = diesel::select(st_square_grid(
+        1.0,
+        st_make_envelope(0.3, 0.2, 4.1, 2.9, 4326),
+    ))
+    .load(&mut conn)
+    .expect("Error generating square grid");
+    squares.sort_by_key(|c| (c.i, c.j));
+    assert_cells_eq(&grid::square_grid(1.0, min, max), &squares);
+
+    let origin = Point::<4326>::new(10.0, 20.0);
+    let hexagon: Polygon<4326, Point<4326>> =
+        diesel::select(st_hexagon_with_origin(2.0, -1, 3, origin))
+            .get_result(&mut conn)
+            .expect("Error generating hexagon");
+    assert_cells_eq(
+        &[GridCell {
+            geom: grid::hexagon(2.0, -1, 3, origin),
+            i: -1,
+            j: 3,
+        }],
+        &[GridCell {
+            geom: hexagon,
+            i: -1,
+            j: 3,
+        }],
+    );
+    let square: Polygon<4326, Point<4326>> =
+        diesel::select(st_set_srid(st_square(2.0, -1, 3), 4326))
+            .get_result(&mut conn)
+            .expect("Error generating square");
+    assert_eq!(grid::square(2.0, -1, 3, Point::new(0.0, 0.0)), square);
+}
+
+#[test]
+fn intersection_matrix_test() {
+    let matrix: IntersectionMatrix = "FF1FF0102".parse().expect("valid matrix");
+    assert_eq!([None, None, Some(1)], matrix.cells[..3]);
+    assert!(matrix.is_disjoint());
+    assert!(!matrix.is_intersects());
+    assert!(matrix.matches("ff*ff****"));
+    assert!(!matrix.matches("FF*FF***"));
+    assert!(!matrix.is_within());
+    assert!("FF1FF010".parse::<IntersectionMatrix>().is_err());
+    assert!("FF1FF010T".parse::<IntersectionMatrix>().is_err());
+
+    let matrix: IntersectionMatrix = "2FFF1FFF2".parse().expect("valid matrix");
+    assert!(matrix.is_equals());
+    assert!(matrix.is_within());
+    assert!(matrix.is_contains());
+    assert!(matrix.is_covers());
+    assert!(matrix.is_covered_by());
+    assert!(!matrix.is_touches());
+}
+
+#[test]
+fn relate_sql_test() {
+    let query = geometry_samples::table.select((
+        st_relate(geometry_samples::point, geometry_samples::polygon),
+        st_relate_pattern(
+            geometry_samples::point,
+            geometry_samples::polygon,
+            "T*F**F***",
+        ),
+    ));
+    assert_eq!(
+        debug_query::<Pg, _>(&query).to_string(),
+        "SELECT ST_Relate(\"geometry_samples\".\"point\", \"geometry_samples\".\"polygon\"), \
+         ST_Relate(\"geometry_samples\".\"point\", \"geometry_samples\".\"polygon\", $1) \
+         FROM \"geometry_samples\" -- binds: [\"T*F**F***\"]"
+    );
+}
+
+#[test]
+fn relate_test() {
+    let mut conn = initialize();
+    let point = Point::<4326>::new(1.0, 1.0);
+    let mut polygon = Polygon::<4326, Point<4326>>::new();
+    polygon.add_points(&[
+        Point::new(0.0, 0.0),
+        Point::new(0.0, 2.0),
+        Point::new(2.0, 2.0),
+        Point::new(2.0, 0.0),
+        Point::new(0.0, 0.0),
+    ]);
+
+    let matrix: IntersectionMatrix = diesel::select(st_relate(point, polygon.clone()))
+        .get_result(&mut conn)
+        .expect("Error relating geometries");
+    assert_eq!("0FFFFF212", matrix.to_string());
+    assert!(matrix.is_within());
+    assert!(!matrix.is_contains());
+
+    let within: bool = diesel::select(st_relate_pattern(point, polygon.clone(), "T*F**F***"))
+        .get_result(&mut conn)
+        .expect("Error relating geometries");
+    assert!(within);
+    let matches: bool = diesel::select(st_relate_match(matrix, "T*F**F***"))
+        .get_result(&mut conn)
+        .expect("Error matching intersection matrix");
+    assert!(matches);
+}
+
+#[test]
+fn nullable_functions_test() {
+    let mut conn = initialize();
+    let polygon = || {
+        new_line::<4326>(vec![
+            (0.0, 0.0),
+            (0.0, 2.0),
+            (2.0, 2.0),
+            (2.0, 0.0),
+            (0.0, 0.0),
+        ])
+        .into_sql::<Geometry>()
+    };
+    let none = || None::<Point<4326>>.into_sql::<Nullable<Geometry>>();
+    let (area, distance, valid): (Option<f64>, Option<f64>, Option<bool>) = diesel::select((
+        st_area(st_make_polygon(polygon()).nullable()),
+        st_distance(polygon(), none()),
+        st_is_valid(none()),
+    ))
+    .get_result(&mut conn)
+    .expect("Error calling functions with nullable geometries");
+    assert_eq!((Some(4.0), None, None), (area, distance, valid));
+
+    let query = distance_samples::table.select((
+        st_area(distance_samples::polygon.nullable()),
+        st_distance(
+            distance_samples::point,
+            distance_samples::polygon.nullable(),
+        ),
+    ));
+    assert_eq!(
+        "SELECT ST_Area(\"distance_samples\".\"polygon\"), \
+         ST_Distance(\"distance_samples\".\"point\", \"distance_samples\".\"polygon\") \
+         FROM \"distance_samples\" -- binds: []",
+        debug_query::<Pg, _>(&query).to_string()
+    );
+}
+
+#[test]
+fn cast_sql_test() {
+    let query = geometry_samples::table.select((
+        st_area_geography(geometry_samples::polygon.to_geography(), true),
+        geometry_samples::point.nullable().to_geography(),
+        geometry_samples::polygon.to_box2d().to_text(),
+        geometry_samples::polygon.to_box3d().to_geometry(),
+    ));
+    assert_eq!(
+        debug_query::<Pg, _>(&query).to_string(),
+        "SELECT ST_Area(CAST(\"geometry_samples\".\"polygon\" AS geography), $1), \
+         CAST(\"geometry_samples\".\"point\" AS geography), \
+         CAST(CAST(\"geometry_samples\".\"polygon\" AS box2d) AS text), \
+         CAST(CAST(\"geometry_samples\".\"polygon\" AS box3d) AS geometry) \
+         FROM \"geometry_samples\" -- binds: [true]"
+    );
+    let query = diesel::select(
+        "SRID=4326;POINT(1 2)"
+            .into_sql::<diesel::sql_types::Text>()
+            .to_geometry(),
+    );
+    assert_eq!(
+        debug_query::<Pg, _>(&query).to_string(),
+        "SELECT CAST($1 AS geometry) -- binds: [\"SRID=4326;POINT(1 2)\"]"
+    );
+}
+
+#[test]
+fn cast_test() {
+    let mut conn = initialize();
+    let sample: NewGeometrySample2D<4326> = NewGeometrySampleG::mock("cast_test").into();
+    let sample_from_db: GeometrySample<4326, Point<4326>> =
+        diesel::insert_into(geometry_samples::table)
+            .values(&sample)
+            .get_result(&mut conn)
+            .expect("Error saving geometry sample");
+    let by_id = geometry_samples::table.filter(geometry_samples::id.eq(sample_from_db.id));
+
+    let (area, geography, envelope): (f64, Option<Point<4326>>, String) = by_id
+        .select((
+            st_area_geography(geometry_samples::polygon.to_geography(), true),
+            geometry_samples::point
+                .nullable()
+                .to_geography()
+                .to_geometry(),
+            geometry_samples::point.to_box2d().to_text(),
+        ))
+        .get_result(&mut conn)
+        .expect("Error casting geometries");
+    assert!(area > 0.0);
+    assert_eq!(Some(sample.point), geography);
+    assert_eq!(
+        format!(
+            "BOX({} {},{} {})",
+            sample.point.x, sample.point.y, sample.point.x, sample.point.y
+        ),
+        envelope
+    );
+
+    let point: Point<4326> = diesel::select(
+        "SRID=4326;POINT(1 2)"
+            .into_sql::<diesel::sql_types::Text>()
+            .to_geometry(),
+    )
+    .get_result(&mut conn)
+    .expect("Error casting text");
+    assert_eq!(Point::new(1.0, 2.0), point);
+
+    let _ = diesel::delete(by_id).execute(&mut conn);
+}
+
+#[test]
+fn within_meters_test() {
+    let mut conn = initialize();
+    // one degree of longitude is about 67.9 km at 52.5°N
+    let samples: Vec<_> = [(13.4059, 52.5), (13.4088, 52.5), (179.999, 0.0)]
+        .iter()
+        .map(|(x, y)| NewDistanceSample {
+            name: String::from("within_meters_test"),
+            point: gps::Point::new(*x, *y),
+            polygon: gps::Polygon::new(),
+        })
+        .collect();
+    diesel::insert_into(distance_samples::table)
+        .values(&samples)
+        .execute(&mut conn)
+        .expect("Error saving distance samples");
+    let by_name = distance_samples::table.filter(distance_samples::name.eq("within_meters_test"));
+
+    let center = gps::Point::new(13.4, 52.5);
+    let found: Vec<(gps::Point, f64)> = by_name
+        .filter(gps::within_meters(distance_samples::point, center, 500.0))
+        .select((
+            distance_samples::point,
+            gps::distance_meters(distance_samples::point, center),
+        ))
+        .load(&mut conn)
+        .expect("Error searching by radius");
+    assert_eq!(1, found.len());
+    assert_eq!(samples[0].point, found[0].0);
+    assert!((found[0].1 - 400.0).abs() < 10.0, "{}", found[0].1);
+
+    // the envelope has to wrap around the antimeridian
+    let found: Vec<gps::Point> = by_name
+        .filter(gps::within_meters(
+            distance_samples::point,
+            gps::Point::new(-179.999, 0.0),
+            500.0,
+        ))
+        .select(distance_samples::point)
+        .load(&mut conn)
+        .expect("Error searching by radius");
+    assert_eq!(vec![samples[2].point], found);
+
+    let found: Vec<Option<f64>> = by_name
+        .filter(gps::within_meters(
+            distance_samples::point.nullable(),
+            center,
+            500.0,
+        ))
+        .select(gps::distance_meters(
+            distance_samples::point.nullable(),
+            center,
+        ))
+        .load(&mut conn)
+        .expect("Error searching nullable geometries by radius");
+    assert_eq!(1, found.len());
+    assert!(found[0].is_some());
+    let distance: Option<f64> = diesel::select(gps::distance_meters(
+        None::<gps::Point>.into_sql::<Nullable<Geometry>>(),
+        center,
+    ))
+    .get_result(&mut conn)
+    .expect("Error computing distance to NULL");
+    assert_eq!(None, distance);
+
+    let _ = diesel::delete(by_name).execute(&mut conn);
+}
@@ -3,8 +3,13 @@ extern crate diesel;
 
 mod common;
 use common::*;
-use diesel::{ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
-use postgis_diesel::{operators::*, types::*};
+use diesel::{
+    debug_query,
+    pg::Pg,
+    sql_types::{BigInt, Text},
+    ExpressionMethods, IntoSql, NullableExpressionMethods, QueryDsl, QueryResult, RunQueryDsl,
+};
+use postgis_diesel::{operators::*, sql_types::Geometry, types::*};
 
 #[test]
 fn distance_2d_test() {
@@ -42,7 +47,7 @@ fn distance_2d_test() {
     let r = diesel::insert_into(distance_samples)
         .values(records)
         .execute(&mut conn);
-    assert_eq!(true, r.is_ok(), "can't insert data");
+    assert!(r.is_ok(), "can't insert data");
 
     use self::distance_samples::dsl::*;
 
@@ -66,33 +71,137 @@ fn distance_2d_test() {
     assert_eq!("Moscow", found_sample.name);
 }
 
+#[derive(QueryableByName)]
+struct QueryPlan {
+    #[diesel(sql_type = Text, column_name = "QUERY PLAN")]
+    line: String,
+}
+
+#[test]
+fn nearest_test() {
+    let mut conn = initialize();
+    let samples: Vec<_> = [(0.0, 0.0), (1.0, 1.0), (5.0, 5.0), (2.0, 2.0)]
+        .iter()
+        .map(|(x, y)| NewDistanceSample {
+            name: String::from("nearest_test"),
+            point: Point::<4326>::new(*x, *y),
+            polygon: Polygon::new(),
+        })
+        .collect();
+    diesel::insert_into(distance_samples::table)
+        .values(&samples)
+        .execute(&mut conn)
+        .expect("Error saving distance samples");
+
+    let target = Point::<4326>::new(1.1, 1.1);
+    let found: Vec<(DistanceSample<4326>, f64)> = nearest(distance_samples::point, target, 2)
+        .filter(distance_samples::name.eq("nearest_test"))
+        .load(&mut conn)
+        .expect("Error finding nearest samples");
+    assert_eq!(2, found.len());
+    assert_eq!(Point::new(1.0, 1.0), found[0].0.point);
+    assert!((found[0].1 - 0.1 * 2f64.sqrt()).abs() < 1e-9);
+    assert_eq!(Point::new(2.0, 2.0), found[1].0.point);
+
+    // the plan must walk the GiST index in distance order instead of sorting
+    let query = nearest(distance_samples::point, target, 2);
+    let sql = debug_query::<Pg, _>(&query).to_string();
+    let sql = sql.split(" -- binds:").next().unwrap();
+    diesel::sql_query("SET enable_seqscan = off")
+        .execute(&mut conn)
+        .expect("Error disabling sequential scans");
+    let plan: Vec<QueryPlan> = diesel::sql_query(format!("EXPLAIN {}", sql))
+        .bind::<Geometry, _>(target)
+        .bind::<Geometry, _>(target)
+        .bind::<BigInt, _>(2)
+        .load(&mut conn)
+        .expect("Error explaining nearest query");
+    let plan: Vec<String> = plan.into_iter().map(|p| p.line).collect();
+    assert!(
+        plan.iter()
+            .any(|line| line.contains("Index Scan using distance_samples_point_idx")),
+        "index not used: {:?}",
+        plan
+    );
+    assert!(
+        plan.iter().all(|line| !line.contains("Sort")),
+        "nearest rows are sorted instead of read in index order: {:?}",
+        plan
+    );
+
+    let _ =
+        diesel::delete(distance_samples::table.filter(distance_samples::name.eq("nearest_test")))
+            .execute(&mut conn);
+}
+
+#[test]
+fn nullable_operands_sql_test() {
+    let query = distance_samples::table
+        .select(distance_2d(
+            distance_samples::point.nullable(),
+            distance_samples::point,
+        ))
+        .filter(
+            intersects_2d(
+                distance_samples::polygon,
+                Some(Point::<4326>::new(1.0, 2.0)),
+            )
+            .assume_not_null(),
+        );
+    assert_eq!(
+        "SELECT \"distance_samples\".\"point\" <-> \"distance_samples\".\"point\" \
+         FROM \"distance_samples\" \
+         WHERE \"distance_samples\".\"polygon\" && $1 \
+         -- binds: [Some(Point { x: 1.0, y: 2.0 })]",
+        debug_query::<Pg, _>(&query).to_string()
+    );
+}
+
+#[test]
+fn nullable_operands_test() {
+    let mut conn = initialize();
+    let none: Option<Point<4326>> = None;
+    let distances: (Option<f64>, Option<f64>) = diesel::select((
+        distance_2d(
+            Point::<4326>::new(0.0, 0.0)
+                .into_sql::<Geometry>()
+                .nullable(),
+            Point::<4326>::new(3.0, 4.0),
+        ),
+        distance_2d(Point::<4326>::new(0.0, 0.0).into_sql::<Geometry>(), none),
+    ))
+    .get_result(&mut conn)
+    .expect("Error calculating distances");
+    assert_eq!((Some(5.0), None), distances);
+}
+
 macro_rules! operator_test {
     ($t:ident; $f:ident; $find:expr; $not_find:expr) => {
         #[test]
         fn $t() {
             let mut conn = initialize();
             let mut polygon = Polygon::new();
-            polygon.add_points(&vec![
+            polygon.add_points(&[
                 new_point(72.0, 64.0),
                 new_point(73.0, 65.0),
                 new_point(71.0, 62.0),
                 new_point(72.0, 64.0),
             ]);
             let mut multiline = MultiLineString::new();
-            multiline.add_points(&vec![new_point(72.0, 64.0), new_point(73.0, 65.0)]);
+            multiline.add_points(&[new_point(72.0, 64.0), new_point(73.0, 65.0)]);
             multiline.add_line();
-            multiline.add_points(&vec![new_point(71.0, 62.0), new_point(72.0, 64.0)]);
+            multiline.add_points(&[new_point(71.0, 62.0), new_point(72.0, 64.0)]);
             let mut multipolygon = MultiPolygon::new();
             multipolygon
                 .add_empty_polygon()
-                .add_points(&vec![
+                .add_points(&[
                     new_point(72.0, 64.0),
                     new_point(73.0, 65.0),
                     new_point(71.0, 62.0),
                     new_point(72.0, 64.0),
                 ])
                 .add_empty_polygon()
-                .add_points(&vec![
+                .add_points(&[
                     new_point(75.0, 64.0),
                     new_point(74.0, 65.0),
                     new_point(74.0, 62.0),
@@ -102,12 +211,12 @@ macro_rules! operator_test {
                 name: String::from(stringify!($t)),
                 point: new_point(71.0, 63.0),
                 linestring: new_line(vec![(72.0, 64.0), (73.0, 64.0)]),
-                polygon: polygon,
+                polygon,
                 multipoint: MultiPoint {
                     points: vec![new_point(72.0, 64.0), new_point(73.0, 64.0)],
                 },
-                multiline: multiline,
-                multipolygon: multipolygon,
+                multiline,
+                multipolygon,
                 geometrycollection: GeometryCollection::new(),
             };
             let _ = diesel::insert_into(geometry_samples::table)
@@ -150,3 +259,14 @@ operator_test!(overlap_or_above_overlaps_test; overlaps_or_above; new_line_4326(
 operator_test!(strictly_above_test; strictly_above; new_line_4326(vec![(72.0, 63.0), (73.0, 63.0)]); new_line_4326(vec![(71.0, 65.0), (71.0, 65.0)]));
 operator_test!(contains_test; contains; new_line_4326(vec![(72.1, 64.0), (72.9, 64.0)]); new_line_4326(vec![(71.0, 64.0), (75.0, 64.0)]));
 operator_test!(bb_same_test; bb_same; new_line_4326(vec![(73.0, 64.0), (72.0, 64.0)]); new_line_4326(vec![(71.0, 64.0), (75.0, 64.0)]));
+
+// the geometry wrappers are operands like the geometries they wrap
+operator_test!(point_any_operand_test; intersects_2d; PointAny::<4326>::new(72.5, 64.0, None, None); PointAny::<4326>::new(71.0, 63.0, None, None));
+operator_test!(dyn_point_operand_test; intersects_2d; DynPoint::new(Some(4326), new_point(72.5, 64.0)); DynPoint::new(Some(4326), new_point(71.0, 63.0)));
+operator_test!(dyn_geometry_operand_test; intersects_2d; DynGeometry::<Point<0>>::new(Some(4326), GeometryContainer::LineString(new_line(vec![(72.0, 63.0), (72.0, 65.0)]))); DynGeometry::<Point<0>>::new(Some(4326), GeometryContainer::LineString(new_line(vec![(71.0, 63.0), (71.0, 65.0)]))));
+operator_test!(lenient_operand_test; intersects_2d; Lenient::<_, AcceptMissing>::new(new_line_4326(vec![(72.0, 63.0), (72.0, 65.0)])); Lenient::<_, AcceptMissing>::new(new_line_4326(vec![(71.0, 63.0), (71.0, 65.0)])));
+operator_test!(force_2d_operand_test; intersects_2d; Force2D::new(new_line_z::<4326>(vec![(72.0, 63.0, 1.0), (72.0, 65.0, 1.0)])); Force2D::new(new_line_z::<4326>(vec![(71.0, 63.0, 1.0), (71.0, 65.0, 1.0)])));
+operator_test!(force_3dz_operand_test; intersects_2d; Force3DZ::new(new_line_4326(vec![(72.0, 63.0), (72.0, 65.0)])); Force3DZ::new(new_line_4326(vec![(71.0, 63.0), (71.0, 65.0)])));
+operator_test!(force_3dm_operand_test; intersects_2d; Force3DM::new(new_line_4326(vec![(72.0, 63.0), (72.0, 65.0)])); Force3DM::new(new_line_4326(vec![(71.0, 63.0), (71.0, 65.0)])));
+operator_test!(force_4d_operand_test; intersects_2d; Force4D::new(new_line_4326(vec![(72.0, 63.0), (72.0, 65.0)])); Force4D::new(new_line_4326(vec![(71.0, 63.0), (71.0, 65.0)])));
+operator_test!(max_depth_operand_test; intersects_2d; MaxDepth::<_, 4>::new(new_line_4326(vec![(72.0, 63.0), (72.0, 65.0)])); MaxDepth::<_, 4>::new(new_line_4326(vec![(71.0, 63.0), (71.0, 65.0)])));